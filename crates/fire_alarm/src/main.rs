@@ -1,6 +1,6 @@
-use std::{net::TcpStream, io::Write};
+use std::sync::{Arc, Mutex};
 
-use gauge::types::{fire_alarm::{FireAlarm, FireAlarmState}, Gauge};
+use gauge::types::{client::GaugeClient, device::DeviceId, fire_alarm::{FireAlarm, FireAlarmCommand, FireAlarmState, FIRE_ALARM_ID}, handshake::{Hello, FEATURE_COMMANDS, FEATURE_STATE}, Gauge, GaugeCommand};
 
 // #[derive(Debug)]
 // struct FireAlarm {
@@ -44,15 +44,72 @@ use gauge::types::{fire_alarm::{FireAlarm, FireAlarmState}, Gauge};
 //     }
 // }
 
+/// Выполняет команду сервера, `Ok(true)`, если состояние изменилось
+fn execute(gauge: &mut FireAlarm, command: FireAlarmCommand) -> Result<bool, String> {
+    match (command, gauge.state()) {
+        (FireAlarmCommand::Enable, FireAlarmState::Disabled) => {
+            gauge.set_state(FireAlarmState::Enabled);
+            Ok(true)
+        },
+        (FireAlarmCommand::Enable, _) => Ok(false),
+        (FireAlarmCommand::Disable, FireAlarmState::Disabled) => Ok(false),
+        (FireAlarmCommand::Disable, _) => {
+            gauge.set_state(FireAlarmState::Disabled);
+            Ok(true)
+        },
+        (FireAlarmCommand::Silence, FireAlarmState::OnAlert) => {
+            gauge.set_state(FireAlarmState::Enabled);
+            Ok(true)
+        },
+        (FireAlarmCommand::Silence, _) => Err("alarm is not sounding".to_string()),
+        (FireAlarmCommand::SelfTest, FireAlarmState::Disabled) => Err("gauge disabled".to_string()),
+        (FireAlarmCommand::SelfTest, _) => {
+            println!("Self-test passed");
+            Ok(false)
+        },
+    }
+}
+
 fn main() {
     let name = std::env::var("GAUGE_NAME")
         .expect("GAUGE_NAME variable not defined!");
     let server_adress= std::env::var("SERVER_ADRESS")
         .expect("SERVER_ADRESS variable not defined!");
-
-    let mut gauge: FireAlarm = FireAlarm::new(name,FireAlarmState::Disabled);
+    // Идентификатор создается при первом запуске и потом не меняется
+    let device_id_file = std::env::var("DEVICE_ID_FILE")
+        .unwrap_or("device_id".to_string());
+    let device = DeviceId::load_or_create(&device_id_file)
+        .expect("Error loading device id");
+    println!("Device id: {device}");
+    let hello = Hello::new(FIRE_ALARM_ID, device.as_str(), &[FEATURE_STATE, FEATURE_COMMANDS]);
+
+    let mut gauge = FireAlarm::new(name,FireAlarmState::Disabled);
+    gauge.set_device(device);
+    let gauge = Arc::new(Mutex::new(gauge));
     let mut updated = false;
 
+    let commanded_gauge = Arc::clone(&gauge);
+    let client = GaugeClient::new(server_adress, hello, move |command, client| {
+        let command = FireAlarmCommand::parse_command(command)
+            .map_err(|e| e.to_string())?;
+        let mut gauge = commanded_gauge.lock().expect("Error locking mutex");
+
+        println!("Server command: {command}");
+
+        if execute(&mut gauge, command)? {
+            println!("{gauge}");
+            gauge.serialize()
+                .and_then(|bytes| client.send(&bytes))
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    });
+
+    if let Err(e) = client.connect() {
+        eprintln!("{}",e)
+    }
+
     loop {
         // Управление датчиком
         println!("P = enable/disable, F = trigger");
@@ -60,6 +117,8 @@ fn main() {
         let mut input = String::new();
         std::io::stdin().read_line(&mut input).expect("Failed to read input");
 
+        let mut gauge = gauge.lock().expect("Error locking mutex");
+
         match input.trim(){
             "P" | "p" => {
                 match gauge.state() {
@@ -98,10 +157,8 @@ fn main() {
 
         // Обработка состояний дадчика
         if updated {
-            let mut stream = TcpStream::connect(server_adress.clone())
-                .expect(format!("Cant connect to listener on {}",server_adress).as_str());
-
-            let request = stream.write_all(&gauge.serialize());
+            let request = gauge.serialize()
+                .and_then(|bytes| client.send(&bytes));
 
             match request {
                 Ok(_) => (),
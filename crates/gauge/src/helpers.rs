@@ -1,7 +1,9 @@
 //! Вспомогательные функции
+use std::fmt::Display;
+
 use json_minimal::Json;
 
-use crate::types::{DeserializedGauge, fire_alarm::{FireAlarm, FIRE_ALARM_ID}, Gauge, temperature_gauge::{TemperatureGauge, TEMPERATURE_GAUGE_ID}};
+use crate::types::{DeserializedGauge, fire_alarm::{FireAlarm, FireAlarmCommand, FIRE_ALARM_ID}, Gauge, GaugeCommand, SerializedCommand, temperature_gauge::{TemperatureGauge, TemperatureGaugeCommand, TEMPERATURE_GAUGE_ID}};
 
 pub enum Gauges {
     FireAlarm(FireAlarm),
@@ -19,38 +21,132 @@ pub fn read_gauge_by_id(gauge: DeserializedGauge) -> super::Result<Gauges> {
     Ok(gauge)
 }
 
+/// Разбирает текстовую команду, например `interval 10`, для датчика типа `id`
+pub fn parse_command_by_id(id: &[u8], command: &str) -> super::Result<SerializedCommand> {
+    let command = match id {
+        FIRE_ALARM_ID => command.parse::<FireAlarmCommand>()?.serialize_command(),
+        TEMPERATURE_GAUGE_ID => command.parse::<TemperatureGaugeCommand>()?.serialize_command(),
+        _ => Err("Unknown gauge type")?
+    };
+
+    Ok(command)
+}
+
 const NUMBER_EXPECTED: &str = "expected to be number";
+const STRING_EXPECTED: &str = "expected to be a string";
+const BOOL_EXPECTED: &str = "expected to be true or false";
 const ARRAY_EXPECTED: &str = "expected to be array";
+const FIELD_REQUIRED: &str = "field required";
 const MALFORMED_JSON: &str = "malformed json";
 const NOT_AN_OBJECT: &str = "json is not an object";
 
+/// Ошибка разбора JSON с путем до поля в виде JSON Pointer,
+/// например `/floors/1/rooms/0/walls/2/position_start/x`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    path: String,
+    message: String,
+}
+
+impl JsonError {
+    /// Ошибка в самом разбираемом значении
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { path: String::new(), message: message.into() }
+    }
+
+    /// Ошибка в поле `field` разбираемого значения
+    pub fn field(field: impl Display, message: impl Into<String>) -> Self {
+        Self::new(message).within(field)
+    }
+
+    /// Путь до поля, пустой для самого значения
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Добавляет `segment` в начало пути,
+    /// когда ошибка поднимается из вложенного значения
+    pub fn within(mut self, segment: impl Display) -> Self {
+        let segment = segment
+            .to_string()
+            .replace('~', "~0")
+            .replace('/', "~1");
+
+        self.path = ["/", &segment, &self.path].concat();
+        self
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Переносит ошибку разбора вложенного значения `segment` на уровень выше.
+/// Ошибки без пути считаются ошибками самого `segment`
+pub fn json_nested(e: crate::Error, segment: impl Display) -> crate::Error {
+    match e.downcast::<JsonError>() {
+        Ok(e) => Box::new(e.within(segment)),
+        Err(e) => Box::new(JsonError::field(segment, e.to_string())),
+    }
+}
+
+/// `on_absence` для обязательного поля
+pub fn json_required<T>(field: &str) -> crate::Result<T> {
+    Err(JsonError::field(field, FIELD_REQUIRED))?
+}
+
 pub fn json_check_object<'a>(value: &'a Json) -> crate::Result<&'a Json> {
     match value {
         Json::OBJECT { name: _, value } => Ok(value.unbox()),
-        _ => Err(NOT_AN_OBJECT)?
+        _ => Err(JsonError::new(NOT_AN_OBJECT))?
     }
 }
 
-pub fn json_check_array<T: for<'a> TryFrom<&'a Json>>(value: &Json, field: &str, on_absence: crate::Result<Vec<T>>) -> crate::Result<Vec<T>> {
+/// Разбирает поле `field` как `T`, ошибки указывают путь внутри поля
+pub fn json_check_field<T>(value: &Json, field: &str, on_absence: crate::Result<T>) -> crate::Result<T>
+where
+    T: for<'a> TryFrom<&'a Json, Error = crate::Error>
+{
+    match value.get(field) {
+        Some(json) => T::try_from(json).map_err(|e| json_nested(e, field)),
+        None => on_absence,
+    }
+}
+
+pub fn json_check_array<T>(value: &Json, field: &str, on_absence: crate::Result<Vec<T>>) -> crate::Result<Vec<T>>
+where
+    T: for<'a> TryFrom<&'a Json, Error = crate::Error>
+{
     let array_json = match value.get(field) {
         Some(json) => match json {
             Json::OBJECT { name: _, value } => value.unbox(),
-            _ => Err(MALFORMED_JSON)?
+            _ => Err(JsonError::field(field, MALFORMED_JSON))?
         }
         None => return on_absence,
     };
 
     let array = match array_json {
         Json::ARRAY(arr) => arr,
-        _ => Err([field,ARRAY_EXPECTED].join(" "))?
+        _ => Err(JsonError::field(field, ARRAY_EXPECTED))?
     };
 
     let mut result: Vec<T> = vec![];
 
-    for val in array {
+    for (idx, val) in array.iter().enumerate() {
         match T::try_from(val) {
             Ok(data) => result.push(data),
-            Err(_e) => Err("Error parsing value in array")?,
+            Err(e) => Err(json_nested(json_nested(e, idx), field))?,
         };
     }
 
@@ -63,12 +159,41 @@ pub fn json_check_number<T: From<f64>>(value: &Json, field: &str, on_absence: cr
             Json::OBJECT { name: _, value } => {
                 match value.unbox() {
                     Json::NUMBER(number) => Ok(T::from(*number)),
-                    _ => Err([field,NUMBER_EXPECTED].join(" "))?
+                    _ => Err(JsonError::field(field, NUMBER_EXPECTED))?
                 }
             },
-            _ => Err(MALFORMED_JSON)?
+            _ => Err(JsonError::field(field, MALFORMED_JSON))?
         },
         None => on_absence,
     }
 }
 
+pub fn json_check_bool(value: &Json, field: &str, on_absence: crate::Result<bool>) -> crate::Result<bool> {
+    match value.get(field) {
+        Some(v) => match v {
+            Json::OBJECT { name: _, value } => {
+                match value.unbox() {
+                    Json::BOOL(bool) => Ok(*bool),
+                    _ => Err(JsonError::field(field, BOOL_EXPECTED))?
+                }
+            },
+            _ => Err(JsonError::field(field, MALFORMED_JSON))?
+        },
+        None => on_absence,
+    }
+}
+
+pub fn json_check_string(value: &Json, field: &str, on_absence: crate::Result<String>) -> crate::Result<String> {
+    match value.get(field) {
+        Some(v) => match v {
+            Json::OBJECT { name: _, value } => {
+                match value.unbox() {
+                    Json::STRING(string) => Ok(string.clone()),
+                    _ => Err(JsonError::field(field, STRING_EXPECTED))?
+                }
+            },
+            _ => Err(JsonError::field(field, MALFORMED_JSON))?
+        },
+        None => on_absence,
+    }
+}
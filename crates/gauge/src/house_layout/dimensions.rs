@@ -2,7 +2,7 @@ use std::ops::{AddAssign, SubAssign};
 
 use json_minimal::Json;
 
-use crate::helpers::json_check_object;
+use crate::helpers::{json_check_number, json_check_object, json_required, JsonError};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Default,Clone, Copy)]
 pub struct Centimeters(u16);
@@ -21,17 +21,18 @@ impl AddAssign for Centimeters {
     }
 }
 
+/// Расстояние не бывает отрицательным, разность ограничена нулем
 impl std::ops::Sub for Centimeters {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Centimeters(self.0 + rhs.0)
+        Centimeters(self.0.saturating_sub(rhs.0))
     }
 }
 
 impl SubAssign for Centimeters {
     fn sub_assign(&mut self, rhs: Self) {
-        self.0 = self.0 - rhs.0
+        self.0 = self.0.saturating_sub(rhs.0)
     }
 }
 
@@ -47,6 +48,12 @@ impl From<f64> for Centimeters {
     }
 }
 
+impl From<Centimeters> for f64 {
+    fn from(value: Centimeters) -> Self {
+        value.0.into()
+    }
+}
+
 impl Into<Json> for Centimeters {
     fn into(self) -> Json {
         Json::NUMBER(self.0.into())
@@ -56,7 +63,7 @@ impl Into<Json> for Centimeters {
 pub struct Meters<'a>(&'a Centimeters);
 
 
-#[derive(Debug,Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Position {
     pub x: Centimeters,
     pub y: Centimeters
@@ -79,28 +86,29 @@ impl TryFrom<&Json> for Position {
     type Error = crate::Error;
 
     fn try_from(value: &Json) -> Result<Self, Self::Error> {
-        const POSITIONS_REQUIRED: &str = "both x and y position requied";
-        const NOT_A_NUMBER: &str = "coordinate must be a number";
-
         let value = json_check_object(value)?;
 
-        let x = value.get("x")
-            .ok_or(POSITIONS_REQUIRED)?;
-        let y = value.get("y")
-            .ok_or(POSITIONS_REQUIRED)?;
+        let x = json_centimeters(value, "x", json_required("x"))?;
+        let y = json_centimeters(value, "y", json_required("y"))?;
 
-        let x: Centimeters = match json_check_object(x)? {
-            Json::NUMBER(n) => (*n).into(),
-            _ => Err(NOT_A_NUMBER)?
-        };
+        Ok( Self { x, y } )
+    }
+}
 
-        let y: Centimeters = match json_check_object(y)? {
-            Json::NUMBER(n) => (*n).into(),
-            _ => Err(NOT_A_NUMBER)?
-        };
+/// Поле `field` с целым числом сантиметров от 0 до 65535,
+/// другие числа отвергаются, а не округляются
+pub(super) fn json_centimeters(value: &Json, field: &str, on_absence: crate::Result<Centimeters>) -> crate::Result<Centimeters> {
+    if value.get(field).is_none() {
+        return on_absence;
+    }
 
-        Ok( Self { x, y } )
+    let number: f64 = json_check_number(value, field, json_required(field))?;
+
+    if !(0.0..=u16::MAX as f64).contains(&number) || number.fract() != 0.0 {
+        Err(JsonError::field(field, "expected to be an integer from 0 to 65535"))?
     }
+
+    Ok(Centimeters(number as u16))
 }
 
 impl<ICX,ICY> From<(ICX,ICY)> for Position
@@ -123,5 +131,11 @@ mod test {
         let second: Centimeters = Centimeters(5);
 
         assert_eq!(first + second, Centimeters(10));
+        assert_eq!(Centimeters(7) - second, Centimeters(2));
+        assert_eq!(Centimeters(3) - second, Centimeters(0));
+
+        let mut third = Centimeters(1);
+        third -= second;
+        assert_eq!(third, Centimeters(0));
     }
 }
@@ -0,0 +1,273 @@
+//! Пути эвакуации с этажа
+//!
+//! Путь идет от центра комнаты через середины дверей к ближайшей
+//! двери, помеченной как выход (см. [`Door::is_exit`](super::wall::Door::is_exit)).
+//! Внутри комнаты между дверьми идут по прямой. Через комнаты,
+//! в которых сработала пожарная сигнализация, путь не проходит,
+//! но из самой такой комнаты выйти можно.
+use json_minimal::Json;
+
+use super::{dimensions::{Centimeters, Position}, floor::Floor, geometry::{between, coordinates, length, midpoint, projection, Coordinates, TOLERANCE}};
+
+/// Путь из одной комнаты
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub room: usize,
+    /// Сработала ли сигнализация в самой комнате
+    pub on_alert: bool,
+    /// Длина пути, `None`, если до выхода не добраться
+    pub distance: Option<Centimeters>,
+    /// Середины дверей по порядку, последняя - выход
+    pub doors: Vec<Position>,
+}
+
+impl Into<Json> for Route {
+    fn into(self) -> Json {
+        Json::JSON(vec![
+            Json::OBJECT {
+                name: "room".to_string(),
+                value: Box::new(Json::NUMBER(self.room as f64))
+            },
+            Json::OBJECT {
+                name: "on_alert".to_string(),
+                value: Box::new(Json::BOOL(self.on_alert))
+            },
+            Json::OBJECT {
+                name: "distance".to_string(),
+                value: Box::new(match self.distance {
+                    Some(distance) => distance.into(),
+                    None => Json::NULL,
+                })
+            },
+            Json::OBJECT {
+                name: "doors".to_string(),
+                value: Box::new(Json::ARRAY(
+                    self.doors
+                        .into_iter()
+                        .map(|d| d.into())
+                        .collect()
+                ))
+            },
+        ])
+    }
+}
+
+/// Дверь как вершина графа путей
+struct Node {
+    position: Coordinates,
+    exit: bool,
+    /// Комнаты, в стенах которых находится дверь
+    rooms: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EvacuationPlan {
+    routes: Vec<Route>,
+}
+
+impl EvacuationPlan {
+    pub fn for_floor(floor: &Floor) -> Self {
+        let rooms = floor.rooms();
+        let nodes = nodes(floor);
+
+        // Дейкстра от выходов, комнаты с тревогой не пропускаем
+        let mut distance: Vec<f64> = nodes
+            .iter()
+            .map(|n| if n.exit { 0.0 } else { f64::INFINITY })
+            .collect();
+        let mut next: Vec<Option<usize>> = vec![None; nodes.len()];
+        let mut visited = vec![false; nodes.len()];
+
+        while let Some(current) = (0..nodes.len())
+            .filter(|i| !visited[*i] && distance[*i].is_finite())
+            .min_by(|a, b| distance[*a].total_cmp(&distance[*b]))
+        {
+            visited[current] = true;
+
+            for (other, node) in nodes.iter().enumerate() {
+                let passable = node.rooms
+                    .iter()
+                    .any(|r| nodes[current].rooms.contains(r) && !rooms[*r].on_alert());
+
+                if visited[other] || !passable {
+                    continue;
+                }
+
+                let candidate = distance[current] + between(node.position, nodes[current].position);
+                if candidate < distance[other] {
+                    distance[other] = candidate;
+                    next[other] = Some(current);
+                }
+            }
+        }
+
+        let routes = rooms
+            .iter()
+            .enumerate()
+            .map(|(idx, room)| {
+                let start = room.polygon()
+                    .ok()
+                    .map(|p| p.centroid());
+
+                let best = start.and_then(|start| {
+                    let start = coordinates(&start);
+
+                    nodes
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, n)| n.rooms.contains(&idx) && distance[*i].is_finite())
+                        .map(|(i, n)| (i, between(start, n.position) + distance[i]))
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                });
+
+                let mut doors = vec![];
+                let mut current = best.map(|(i, _)| i);
+                while let Some(node) = current {
+                    doors.push(nodes[node].position.into());
+                    current = next[node];
+                }
+
+                Route {
+                    room: idx,
+                    on_alert: room.on_alert(),
+                    distance: best.map(|(_, d)| d.into()),
+                    doors,
+                }
+            })
+            .collect();
+
+        Self { routes }
+    }
+
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+}
+
+impl Into<Json> for EvacuationPlan {
+    fn into(self) -> Json {
+        Json::JSON(vec![
+            Json::OBJECT {
+                name: "routes".to_string(),
+                value: Box::new(Json::ARRAY(
+                    self.routes
+                        .into_iter()
+                        .map(|r| r.into())
+                        .collect()
+                ))
+            },
+        ])
+    }
+}
+
+/// Двери этажа, дверь, описанная в стенах обеих комнат, считается одной
+fn nodes(floor: &Floor) -> Vec<Node> {
+    let mut nodes: Vec<Node> = vec![];
+
+    for door in floor.rooms().iter().flat_map(|r| &r.walls).flat_map(|w| w.doors()) {
+        let position = midpoint(door.position_start(), door.position_end());
+
+        match nodes.iter_mut().find(|n| between(n.position, position) <= TOLERANCE) {
+            Some(node) => node.exit |= door.is_exit(),
+            None => nodes.push(Node { position, exit: door.is_exit(), rooms: vec![] }),
+        }
+    }
+
+    for node in &mut nodes {
+        node.rooms = floor.rooms()
+            .iter()
+            .enumerate()
+            .filter(|(_, room)| room.walls
+                .iter()
+                .any(|w| projection(w, node.position).is_some_and(|t| 0.0 < t && t < length(w)))
+            )
+            .map(|(idx, _)| idx)
+            .collect();
+    }
+
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{house_layout::{floor::Floor, room::Room, wall::{Door, Wall}}, types::{fire_alarm::{FireAlarm, FireAlarmState}, Gauge, GaugeJson}};
+
+    use super::EvacuationPlan;
+
+    fn room(points: Vec<(u16, u16)>) -> Room {
+        Room::new(
+            Wall::from_connections(points.into_iter().map(|p| p.into()).collect()),
+            250.into(),
+            vec![]
+        )
+    }
+
+    /// Коридор 0 с выходом слева, из него двери в комнаты 1 и 2,
+    /// из комнаты 1 еще дверь в комнату 2
+    fn floor() -> Floor {
+        let mut hall = room(vec![(0, 0), (600, 0), (600, 200), (0, 200)]);
+        hall.walls[3].doors_mut().push(Door::new((0, 150).into(), (0, 50).into()).exit());
+        hall.walls[2].doors_mut().push(Door::new((150, 200).into(), (50, 200).into()));
+        hall.walls[2].doors_mut().push(Door::new((550, 200).into(), (450, 200).into()));
+
+        let mut left = room(vec![(0, 200), (300, 200), (300, 500), (0, 500)]);
+        left.walls[1].doors_mut().push(Door::new((300, 400).into(), (300, 480).into()));
+
+        Floor::new(vec![
+            hall,
+            left,
+            room(vec![(300, 200), (600, 200), (600, 500), (300, 500)]),
+        ], 0)
+    }
+
+    #[test]
+    fn shortest_route() {
+        let plan = EvacuationPlan::for_floor(&floor());
+        let routes = plan.routes();
+
+        assert_eq!(routes[0].doors, vec![(0, 100).into()]);
+        assert_eq!(routes[0].distance, Some(300.into()));
+        // Через левую комнату короче, чем через свою дверь в коридор
+        assert_eq!(routes[2].doors, vec![(300, 440).into(), (100, 200).into(), (0, 100).into()]);
+    }
+
+    #[test]
+    fn avoids_rooms_on_alert() {
+        let on_alert = |room: usize| {
+            let mut floor = floor();
+            let alarm = FireAlarm::new("Alarm".to_string(), FireAlarmState::OnAlert);
+            floor.room_mut(room).unwrap().gauges.push(GaugeJson::new(&alarm.id(), alarm.name(), alarm.state()));
+
+            EvacuationPlan::for_floor(&floor).routes().to_vec()
+        };
+
+        let routes = on_alert(1);
+        assert!(routes[1].on_alert);
+        assert!(routes[1].distance.is_some());
+        assert_eq!(routes[2].doors, vec![(500, 200).into(), (0, 100).into()]);
+
+        let routes = on_alert(0);
+        assert!(routes[0].distance.is_some());
+        assert!(routes[1].distance.is_none());
+        assert!(routes[2].doors.is_empty());
+    }
+
+    /// Середина двери на наклонной стене не попадает в целые сантиметры
+    #[test]
+    fn door_on_diagonal_wall() {
+        let mut lower = room(vec![(0, 0), (300, 200), (0, 200)]);
+        lower.walls[2].doors_mut().push(Door::new((0, 150).into(), (0, 50).into()).exit());
+
+        let mut upper = room(vec![(0, 0), (300, 0), (300, 200)]);
+        let door = Door::new((30, 20).into(), (135, 90).into());
+        assert!(upper.walls[2].check_door(&door).is_ok());
+        assert!(upper.walls[2].check_door(&Door::new((30, 20).into(), (135, 95).into())).is_err());
+        upper.walls[2].doors_mut().push(door);
+
+        let plan = EvacuationPlan::for_floor(&Floor::new(vec![lower, upper], 0));
+        let route = &plan.routes()[1];
+
+        assert_eq!(route.doors, vec![(82.5, 55.0).into(), (0, 100).into()]);
+        assert!(route.distance.is_some());
+    }
+}
@@ -1,6 +1,8 @@
 use json_minimal::Json;
 
-use super::room::Room;
+use crate::helpers::{json_check_array, json_check_number, json_nested, json_required, JsonError};
+
+use super::{dimensions::Position, room::Room};
 
 
 #[derive(Clone, Debug)]
@@ -11,6 +13,62 @@ pub struct Floor {
 
 impl Floor {
     pub fn new(rooms: Vec<Room>, number: u8) -> Self { Self { rooms, number } }
+
+    pub fn add_room(&mut self, room: Room) {
+        self.rooms.push(room)
+    }
+
+    pub fn rooms(&self) -> &Vec<Room> {
+        &self.rooms
+    }
+
+    pub fn room(&self, idx: usize) -> Option<&Room> {
+        self.rooms.get(idx)
+    }
+
+    pub fn room_mut(&mut self, idx: usize) -> Option<&mut Room> {
+        self.rooms.get_mut(idx)
+    }
+
+    /// Комната, внутри которой находится `position`, вместе с ее индексом.
+    /// Если точка лежит на общей стене, возвращается первая из комнат
+    pub fn room_at(&self, position: &Position) -> Option<(usize, &Room)> {
+        self.rooms
+            .iter()
+            .enumerate()
+            .find(|(_, room)| room.contains(position))
+    }
+
+    pub fn remove_room(&mut self, idx: usize) -> Option<Room> {
+        (idx < self.rooms.len()).then(|| self.rooms.remove(idx))
+    }
+
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Проверяет геометрию всех комнат (см. [`Room::validate`])
+    pub fn validate(&self) -> crate::Result<()> {
+        for (idx, room) in self.rooms.iter().enumerate() {
+            room.validate()
+                .map_err(|e| json_nested(json_nested(e, idx), "rooms"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Обновляет `number`, если он указан
+    pub fn patch(&mut self, value: &Json) -> crate::Result<()> {
+        let number: f64 = json_check_number(value, "number", Ok(self.number.into()))?;
+
+        if !(0.0..=u8::MAX as f64).contains(&number) || number.fract() != 0.0 {
+            Err(JsonError::field("number", "expected to be an integer from 0 to 255"))?
+        }
+
+        self.number = number as u8;
+
+        Ok(())
+    }
 }
 
 impl Into<Json> for Floor {
@@ -40,6 +98,25 @@ impl Into<Json> for Floor {
     }
 }
 
+impl TryFrom<&Json> for Floor {
+    type Error = crate::Error;
+
+    /// Requered fields:
+    /// `number`
+    /// Additional fields:
+    /// `rooms`
+    fn try_from(value: &Json) -> Result<Self, Self::Error> {
+        json_check_number::<f64>(value, "number", json_required("number"))?;
+        let rooms: Vec<Room> =
+            json_check_array(value, "rooms", Ok(vec![]))?;
+
+        let mut floor = Self { rooms, number: 0 };
+        floor.patch(value)?;
+
+        Ok(floor)
+    }
+}
+
 impl Default for Floor {
     fn default() -> Self {
         Self { rooms: vec![ Room::default() ], number: 0 }
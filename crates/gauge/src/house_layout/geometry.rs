@@ -0,0 +1,381 @@
+//! Геометрия комнаты
+//!
+//! Стены комнаты должны соединяться концами в один замкнутый
+//! многоугольник без самопересечений. Порядок стен и направление
+//! каждой из них не важны, многоугольник собирается обходом по концам.
+use std::fmt::Display;
+
+use json_minimal::Json;
+
+use super::{dimensions::{Centimeters, Position}, wall::Wall};
+
+/// Квадратных сантиметров в квадратном метре
+const CM2_IN_M2: f64 = 10_000.0;
+/// Кубических сантиметров в кубическом метре
+const CM3_IN_M3: f64 = 1_000_000.0;
+/// Сантиметров в метре
+const CM_IN_M: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    /// Меньше трех стен не образуют комнату
+    TooFewWalls,
+    /// Стена с совпадающими началом и концом
+    ZeroLengthWall(usize),
+    /// Ни одна стена не продолжает стену `wall`
+    OpenPolygon { wall: usize },
+    /// Многоугольник замкнулся, но стена `wall` в него не вошла
+    Disconnected { wall: usize },
+    /// Стены пересекаются или накладываются
+    SelfIntersection(usize, usize),
+}
+
+impl GeometryError {
+    /// Стена, из-за которой многоугольник некорректен
+    pub fn wall(&self) -> Option<usize> {
+        match self {
+            GeometryError::TooFewWalls => None,
+            GeometryError::ZeroLengthWall(wall) => Some(*wall),
+            GeometryError::OpenPolygon { wall } => Some(*wall),
+            GeometryError::Disconnected { wall } => Some(*wall),
+            GeometryError::SelfIntersection(wall, _) => Some(*wall),
+        }
+    }
+}
+
+impl Display for GeometryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeometryError::TooFewWalls =>
+                write!(f, "room needs at least 3 walls"),
+            GeometryError::ZeroLengthWall(wall) =>
+                write!(f, "wall {wall} has zero length"),
+            GeometryError::OpenPolygon { wall } =>
+                write!(f, "no wall continues wall {wall}, room polygon is open"),
+            GeometryError::Disconnected { wall } =>
+                write!(f, "wall {wall} is not connected to the room polygon"),
+            GeometryError::SelfIntersection(a, b) =>
+                write!(f, "walls {a} and {b} intersect"),
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// Замкнутый многоугольник комнаты
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    /// Вершины в порядке обхода
+    vertices: Vec<Position>,
+    /// Индекс стены для каждой стороны `vertices[i]..vertices[i+1]`
+    walls: Vec<usize>,
+}
+
+impl Polygon {
+    pub fn from_walls(walls: &[Wall]) -> Result<Self, GeometryError> {
+        if let Some(idx) = walls.iter().position(|w| w.position_start() == w.position_end()) {
+            return Err(GeometryError::ZeroLengthWall(idx));
+        }
+
+        if walls.len() < 3 {
+            return Err(GeometryError::TooFewWalls);
+        }
+
+        let mut used = vec![false; walls.len()];
+        let mut vertices = vec![walls[0].position_start().clone()];
+        let mut order = vec![0];
+        let mut end = walls[0].position_end();
+        used[0] = true;
+
+        while end != &vertices[0] {
+            let last = *order.last().expect("polygon starts with a wall");
+
+            let (idx, next) = walls
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !used[*i])
+                .find_map(|(i, w)| {
+                    if w.position_start() == end {
+                        Some((i, w.position_end()))
+                    } else if w.position_end() == end {
+                        Some((i, w.position_start()))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(GeometryError::OpenPolygon { wall: last })?;
+
+            used[idx] = true;
+            vertices.push(end.clone());
+            order.push(idx);
+            end = next;
+        }
+
+        if let Some(idx) = used.iter().position(|u| !u) {
+            return Err(GeometryError::Disconnected { wall: idx });
+        }
+
+        let polygon = Self { vertices, walls: order };
+        polygon.check_intersections()?;
+
+        Ok(polygon)
+    }
+
+    pub fn vertices(&self) -> &[Position] {
+        &self.vertices
+    }
+
+    /// Площадь в квадратных метрах
+    pub fn area(&self) -> f64 {
+        let twice_area: i64 = self.sides()
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum();
+
+        twice_area.abs() as f64 / 2.0 / CM2_IN_M2
+    }
+
+    /// Периметр в метрах
+    pub fn perimeter(&self) -> f64 {
+        self.sides()
+            .map(|(a, b)| ((b.0 - a.0) as f64).hypot((b.1 - a.1) as f64))
+            .sum::<f64>() / CM_IN_M
+    }
+
+    /// Объем призмы высотой `height` в кубических метрах
+    pub fn volume(&self, height: Centimeters) -> f64 {
+        self.area() * CM2_IN_M2 * f64::from(height) / CM3_IN_M3
+    }
+
+    /// Центр масс многоугольника
+    pub fn centroid(&self) -> Position {
+        let (mut x, mut y, mut twice_area) = (0.0, 0.0, 0.0);
+
+        for (a, b) in self.sides() {
+            let cross = (a.0 * b.1 - b.0 * a.1) as f64;
+
+            twice_area += cross;
+            x += (a.0 + b.0) as f64 * cross;
+            y += (a.1 + b.1) as f64 * cross;
+        }
+
+        (x / (3.0 * twice_area), y / (3.0 * twice_area)).into()
+    }
+
+    /// Лежит ли `position` внутри многоугольника или на его границе
+    pub fn contains(&self, position: &Position) -> bool {
+        let p = point(position);
+        let mut inside = false;
+
+        for (a, b) in self.sides() {
+            if orientation(a, b, p) == 0 && on_segment(a, b, p) {
+                return true;
+            }
+
+            // Луч вправо от `p` пересекает сторону
+            if (a.1 > p.1) != (b.1 > p.1) {
+                let x = a.0 as f64 + (p.1 - a.1) as f64 * (b.0 - a.0) as f64 / (b.1 - a.1) as f64;
+                if (p.0 as f64) < x {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+
+    /// Стороны многоугольника в целых сантиметрах
+    fn sides(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let len = self.vertices.len();
+
+        (0..len).map(move |i| (
+            point(&self.vertices[i]),
+            point(&self.vertices[(i + 1) % len])
+        ))
+    }
+
+    fn check_intersections(&self) -> Result<(), GeometryError> {
+        let sides: Vec<(Point, Point)> = self.sides().collect();
+        let len = sides.len();
+
+        for i in 0..len {
+            for j in (i + 1)..len {
+                let (a, b) = sides[i];
+                let (c, d) = sides[j];
+
+                let adjacent = j == i + 1 || (i == 0 && j == len - 1);
+
+                let intersect = match adjacent {
+                    // Соседние стороны делят вершину, пересечением считается только наложение
+                    true => {
+                        let (shared, p, q) = if j == i + 1 { (b, a, d) } else { (a, b, c) };
+                        orientation(p, shared, q) == 0
+                            && (q.0 - shared.0) * (p.0 - shared.0) + (q.1 - shared.1) * (p.1 - shared.1) > 0
+                    },
+                    false => segments_intersect(a, b, c, d),
+                };
+
+                if intersect {
+                    return Err(GeometryError::SelfIntersection(self.walls[i], self.walls[j]));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Into<Json> for Polygon {
+    fn into(self) -> Json {
+        let perimeter = self.perimeter();
+        let area = self.area();
+
+        Json::JSON(vec![
+            Json::OBJECT {
+                name: "vertices".to_string(),
+                value: Box::new(Json::ARRAY(
+                    self.vertices
+                        .into_iter()
+                        .map(|v| v.into())
+                        .collect()
+                ))
+            },
+            Json::OBJECT {
+                name: "area".to_string(),
+                value: Box::new(Json::NUMBER(area))
+            },
+            Json::OBJECT {
+                name: "perimeter".to_string(),
+                value: Box::new(Json::NUMBER(perimeter))
+            },
+        ])
+    }
+}
+
+pub(super) type Point = (i64, i64);
+/// Точка с дробными координатами, например середина двери
+pub(super) type Coordinates = (f64, f64);
+
+pub(super) fn point(position: &Position) -> Point {
+    (f64::from(position.x) as i64, f64::from(position.y) as i64)
+}
+
+/// Знак векторного произведения `ab` и `ac`
+pub(super) fn orientation(a: Point, b: Point, c: Point) -> i64 {
+    ((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)).signum()
+}
+
+/// Насколько точка может отстоять от прямой стены, оставаясь на ней, в сантиметрах.
+/// Середина двери на наклонной стене обычно не попадает в целые сантиметры
+pub(super) const TOLERANCE: f64 = 1.0;
+
+/// Расстояние от начала `wall` до проекции `p` на прямую стены,
+/// если `p` отстоит от прямой не дальше чем на [`TOLERANCE`]
+pub(super) fn projection(wall: &Wall, p: Coordinates) -> Option<f64> {
+    let a = coordinates(wall.position_start());
+    let b = coordinates(wall.position_end());
+    let length = (b.0 - a.0).hypot(b.1 - a.1);
+
+    if length == 0.0 {
+        return None;
+    }
+
+    let cross = (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+    if cross.abs() / length > TOLERANCE {
+        return None;
+    }
+
+    let dot = (p.0 - a.0) * (b.0 - a.0) + (p.1 - a.1) * (b.1 - a.1);
+
+    Some(dot / length)
+}
+
+pub(super) fn length(wall: &Wall) -> f64 {
+    let a = point(wall.position_start());
+    let b = point(wall.position_end());
+
+    ((b.0 - a.0) as f64).hypot((b.1 - a.1) as f64)
+}
+
+pub(super) fn between(a: Coordinates, b: Coordinates) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+pub(super) fn coordinates(position: &Position) -> Coordinates {
+    (f64::from(position.x), f64::from(position.y))
+}
+
+/// Середина двери без округления
+pub(super) fn midpoint(a: &Position, b: &Position) -> Coordinates {
+    let (a, b) = (coordinates(a), coordinates(b));
+
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+}
+
+/// `p` лежит в прямоугольнике, построенном на `a` и `b`
+fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    a.0.min(b.0) <= p.0 && p.0 <= a.0.max(b.0)
+        && a.1.min(b.1) <= p.1 && p.1 <= a.1.max(b.1)
+}
+
+/// Пересекаются ли отрезки `ab` и `cd`, включая касание
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let o1 = orientation(a, b, c);
+    let o2 = orientation(a, b, d);
+    let o3 = orientation(c, d, a);
+    let o4 = orientation(c, d, b);
+
+    (o1 != o2 && o3 != o4)
+        || (o1 == 0 && on_segment(a, b, c))
+        || (o2 == 0 && on_segment(a, b, d))
+        || (o3 == 0 && on_segment(c, d, a))
+        || (o4 == 0 && on_segment(c, d, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::house_layout::wall::Wall;
+
+    use super::{GeometryError, Polygon};
+
+    fn walls(points: &[(u16, u16)]) -> Vec<Wall> {
+        Wall::from_connections(points.iter().map(|p| (*p).into()).collect())
+    }
+
+    #[test]
+    fn rectangle() {
+        let mut walls = walls(&[(0, 0), (400, 0), (400, 300), (0, 300)]);
+        // Порядок и направление стен не важны
+        walls.swap(1, 3);
+        walls[2] = Wall::new(
+            walls[2].position_end().clone(),
+            walls[2].position_start().clone(),
+            None, None, None
+        );
+
+        let polygon = Polygon::from_walls(&walls).unwrap();
+
+        assert_eq!(polygon.area(), 12.0);
+        assert_eq!(polygon.perimeter(), 14.0);
+        assert_eq!(polygon.volume(250.into()), 30.0);
+        assert_eq!(polygon.centroid(), (200, 150).into());
+        assert!(polygon.contains(&(100, 100).into()));
+        assert!(polygon.contains(&(400, 100).into()));
+        assert!(!polygon.contains(&(500, 100).into()));
+    }
+
+    #[test]
+    fn invalid_polygons() {
+        let mut open = walls(&[(0, 0), (400, 0), (400, 300), (0, 300)]);
+        open.pop();
+        assert_eq!(Polygon::from_walls(&open).unwrap_err(), GeometryError::OpenPolygon { wall: 2 });
+
+        let bow_tie = walls(&[(0, 0), (400, 300), (400, 0), (0, 300)]);
+        assert!(matches!(Polygon::from_walls(&bow_tie), Err(GeometryError::SelfIntersection(..))));
+
+        let zero = vec![Wall::default()];
+        assert_eq!(Polygon::from_walls(&zero).unwrap_err(), GeometryError::ZeroLengthWall(0));
+
+        let spike = walls(&[(0, 0), (400, 0), (200, 0), (200, 300)]);
+        assert!(matches!(Polygon::from_walls(&spike), Err(GeometryError::SelfIntersection(..))));
+    }
+}
@@ -0,0 +1,233 @@
+//! Граф смежности комнат этажа
+//!
+//! Комнаты смежны, если их стены лежат на одной прямой
+//! и перекрываются на отрезке ненулевой длины. Смежные комнаты
+//! соединены проходом, если на общем отрезке есть дверь
+//! одной из этих стен.
+use std::collections::VecDeque;
+
+use json_minimal::Json;
+
+use super::{dimensions::Centimeters, floor::Floor, geometry::{between, coordinates, length, midpoint, projection, Coordinates, TOLERANCE}, wall::Wall};
+
+/// Общая стена двух комнат
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjacency {
+    /// Индексы комнат на этаже, `rooms.0 < rooms.1`
+    pub rooms: (usize, usize),
+    /// Суммарная длина общих отрезков стен
+    pub shared: Centimeters,
+    /// Сколько дверей ведет из одной комнаты в другую
+    pub doors: usize,
+}
+
+impl Adjacency {
+    /// Можно ли пройти из одной комнаты в другую
+    pub fn connected(&self) -> bool {
+        self.doors > 0
+    }
+}
+
+impl Into<Json> for Adjacency {
+    fn into(self) -> Json {
+        Json::JSON(vec![
+            Json::OBJECT {
+                name: "rooms".to_string(),
+                value: Box::new(Json::ARRAY(vec![
+                    Json::NUMBER(self.rooms.0 as f64),
+                    Json::NUMBER(self.rooms.1 as f64),
+                ]))
+            },
+            Json::OBJECT {
+                name: "shared".to_string(),
+                value: Box::new(self.shared.into())
+            },
+            Json::OBJECT {
+                name: "doors".to_string(),
+                value: Box::new(Json::NUMBER(self.doors as f64))
+            },
+        ])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoomGraph {
+    rooms: usize,
+    edges: Vec<Adjacency>,
+}
+
+impl RoomGraph {
+    pub fn from_floor(floor: &Floor) -> Self {
+        let rooms = floor.rooms();
+        let mut edges = vec![];
+
+        for a in 0..rooms.len() {
+            for b in (a + 1)..rooms.len() {
+                let mut shared = 0.0;
+                // Середины дверей, дверь, описанная в стенах обеих комнат, считается одной
+                let mut doors: Vec<Coordinates> = vec![];
+
+                for wall_a in &rooms[a].walls {
+                    for wall_b in &rooms[b].walls {
+                        let Some((from, to)) = overlap(wall_a, wall_b) else {
+                            continue;
+                        };
+
+                        shared += to - from;
+
+                        let middles = [wall_a, wall_b]
+                            .into_iter()
+                            .flat_map(|w| w.doors())
+                            .map(|d| midpoint(d.position_start(), d.position_end()))
+                            .filter(|middle| projection(wall_a, *middle).is_some_and(|t| from < t && t < to));
+
+                        for middle in middles {
+                            if !doors.iter().any(|d| between(*d, middle) <= TOLERANCE) {
+                                doors.push(middle);
+                            }
+                        }
+                    }
+                }
+
+                if shared > 0.0 {
+                    edges.push(Adjacency { rooms: (a, b), shared: shared.into(), doors: doors.len() });
+                }
+            }
+        }
+
+        Self { rooms: rooms.len(), edges }
+    }
+
+    pub fn edges(&self) -> &[Adjacency] {
+        &self.edges
+    }
+
+    /// Комнаты с общей стеной, с дверью или без
+    pub fn neighbours(&self, room: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter_map(move |e| match e.rooms {
+                (a, b) if a == room => Some(b),
+                (a, b) if b == room => Some(a),
+                _ => None,
+            })
+    }
+
+    /// Комнаты, в которые можно пройти из `room` через двери, включая саму `room`
+    pub fn reachable(&self, room: usize) -> Vec<usize> {
+        if room >= self.rooms {
+            return vec![];
+        }
+
+        let mut visited = vec![false; self.rooms];
+        let mut queue = VecDeque::from([room]);
+        visited[room] = true;
+
+        while let Some(current) = queue.pop_front() {
+            let next: Vec<usize> = self.edges
+                .iter()
+                .filter(|e| e.connected())
+                .filter_map(|e| match e.rooms {
+                    (a, b) if a == current => Some(b),
+                    (a, b) if b == current => Some(a),
+                    _ => None,
+                })
+                .collect();
+
+            for room in next {
+                if !visited[room] {
+                    visited[room] = true;
+                    queue.push_back(room);
+                }
+            }
+        }
+
+        (0..self.rooms).filter(|r| visited[*r]).collect()
+    }
+}
+
+impl Into<Json> for RoomGraph {
+    fn into(self) -> Json {
+        Json::JSON(vec![
+            Json::OBJECT {
+                name: "rooms".to_string(),
+                value: Box::new(Json::NUMBER(self.rooms as f64))
+            },
+            Json::OBJECT {
+                name: "edges".to_string(),
+                value: Box::new(Json::ARRAY(
+                    self.edges
+                        .into_iter()
+                        .map(|e| e.into())
+                        .collect()
+                ))
+            },
+        ])
+    }
+}
+
+/// Общий отрезок стен `a` и `b` в сантиметрах от начала `a`
+fn overlap(a: &Wall, b: &Wall) -> Option<(f64, f64)> {
+    let start = projection(a, coordinates(b.position_start()))?;
+    let end = projection(a, coordinates(b.position_end()))?;
+
+    let from = start.min(end).max(0.0);
+    let to = start.max(end).min(length(a));
+
+    (to > from).then_some((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::house_layout::{floor::Floor, room::Room, wall::{Door, Wall}};
+
+    use super::RoomGraph;
+
+    fn room(points: Vec<(u16, u16)>) -> Room {
+        Room::new(
+            Wall::from_connections(points.into_iter().map(|p| p.into()).collect()),
+            250.into(),
+            vec![]
+        )
+    }
+
+    #[test]
+    fn shared_walls_and_doors() {
+        let mut hall = room(vec![(0, 0), (400, 0), (400, 600), (0, 600)]);
+        // Дверь в стене (400,0)-(400,600), ведет в кухню
+        hall.walls[1].doors_mut().push(Door::new((400, 100).into(), (400, 180).into()));
+
+        let floor = Floor::new(vec![
+            hall,
+            room(vec![(400, 0), (700, 0), (700, 300), (400, 300)]),
+            room(vec![(400, 300), (700, 300), (700, 600), (400, 600)]),
+            room(vec![(800, 0), (900, 0), (900, 100), (800, 100)]),
+        ], 0);
+
+        let graph = RoomGraph::from_floor(&floor);
+
+        assert_eq!(graph.edges().len(), 3);
+        assert_eq!(graph.neighbours(0).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(graph.edges()[0].shared, 300.into());
+        assert!(graph.edges()[0].connected());
+        assert!(!graph.edges()[1].connected());
+
+        assert_eq!(graph.reachable(0), vec![0, 1]);
+        assert_eq!(graph.reachable(3), vec![3]);
+    }
+
+    #[test]
+    fn door_described_by_both_rooms() {
+        let mut hall = room(vec![(0, 0), (400, 0), (400, 300), (0, 300)]);
+        hall.walls[1].doors_mut().push(Door::new((400, 100).into(), (400, 180).into()));
+        hall.walls[1].doors_mut().push(Door::new((400, 200).into(), (400, 280).into()));
+
+        // Та же первая дверь со стороны кухни, в другом направлении
+        let mut kitchen = room(vec![(400, 0), (700, 0), (700, 300), (400, 300)]);
+        kitchen.walls[3].doors_mut().push(Door::new((400, 180).into(), (400, 100).into()));
+
+        let graph = RoomGraph::from_floor(&Floor::new(vec![hall, kitchen], 0));
+
+        assert_eq!(graph.edges()[0].doors, 2);
+    }
+}
@@ -1,5 +1,7 @@
 use json_minimal::Json;
 
+use crate::{helpers::{json_check_array, json_nested, json_required, JsonError}, types::device::DeviceId};
+
 use super::floor::Floor;
 
 
@@ -11,19 +13,85 @@ pub struct House {
 impl House {
     pub fn new(floors: Vec<Floor>) -> Self { Self { floors } }
 
-    pub fn push_floor(&mut self) {
+    /// Добавляет этаж над самым верхним, номера этажей не обязательно идут подряд.
+    /// Возвращает `None`, если выше этажа с номером 255 добавить нельзя
+    pub fn push_floor(&mut self) -> Option<&Floor> {
+        let number = match self.floors.iter().map(|f| f.number).max() {
+            Some(top) => top.checked_add(1)?,
+            None => 0,
+        };
+
         self.floors
             .push(
                 Floor {
-                    number: self.floors.len() as u8,
+                    number,
                     ..Default::default()
                 }
-            )
+            );
+
+        self.floors.last()
     }
 
     pub fn floors(&self) -> &Vec<Floor> {
         &self.floors
     }
+
+    pub fn floor(&self, number: usize) -> Option<&Floor> {
+        self.floors.get(number)
+    }
+
+    pub fn floor_mut(&mut self, number: usize) -> Option<&mut Floor> {
+        self.floors.get_mut(number)
+    }
+
+    /// Проверяет геометрию всех комнат (см. [`Floor::validate`])
+    /// и что номера этажей не повторяются
+    pub fn validate(&self) -> crate::Result<()> {
+        for (idx, floor) in self.floors.iter().enumerate() {
+            if self.floors[..idx].iter().any(|f| f.number == floor.number) {
+                Err(
+                    JsonError::field("number", "floor with this number already exists")
+                        .within(idx)
+                        .within("floors")
+                )?
+            }
+
+            floor.validate()
+                .map_err(|e| json_nested(json_nested(e, idx), "floors"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Где установлено устройство: этаж, комната и номер датчика в комнате
+    pub fn locate_device(&self, device: &DeviceId) -> Option<(usize, usize, usize)> {
+        self.devices()
+            .find(|(_, d)| d == device)
+            .map(|(location, _)| location)
+    }
+
+    /// Устройство, привязанное сразу к нескольким датчикам
+    pub fn duplicate_device(&self) -> Option<DeviceId> {
+        let mut seen = std::collections::HashSet::new();
+
+        self.devices()
+            .map(|(_, device)| device)
+            .find(|device| !seen.insert(device.clone()))
+    }
+
+    fn devices(&self) -> impl Iterator<Item = ((usize, usize, usize), DeviceId)> + '_ {
+        self.floors.iter().enumerate().flat_map(|(f, floor)|
+            floor.rooms().iter().enumerate().flat_map(move |(r, room)|
+                room.gauges.iter().enumerate().filter_map(move |(g, gauge)|
+                    gauge.device().map(|device| ((f, r, g), device))
+                )
+            )
+        )
+    }
+
+    pub fn remove_floor(&mut self, number: usize) -> Option<Floor> {
+        (number < self.floors.len()).then(|| self.floors.remove(number))
+    }
 }
 
 impl Into<Json> for House {
@@ -41,6 +109,19 @@ impl Into<Json> for House {
     }
 }
 
+impl TryFrom<&Json> for House {
+    type Error = crate::Error;
+
+    /// Requered fields:
+    /// `floors`
+    fn try_from(value: &Json) -> Result<Self, Self::Error> {
+        let floors: Vec<Floor> =
+            json_check_array(value, "floors", json_required("floors"))?;
+
+        Ok(Self { floors })
+    }
+}
+
 impl Default for House {
     fn default() -> Self {
         Self { floors: vec![ Floor::default() ] }
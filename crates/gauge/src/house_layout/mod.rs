@@ -4,6 +4,10 @@ pub mod dimensions;
 pub mod wall;
 pub mod room;
 pub mod floor;
+pub mod geometry;
+pub mod graph;
+pub mod evacuation;
+pub mod svg;
 pub mod house;
 
 type Connection = Position;
@@ -36,6 +40,147 @@ mod tests {
         assert!(true)
     }
 
+    #[test]
+    fn wall_json_round_trip_and_patch() {
+        use json_minimal::Json;
+
+        use crate::house_layout::wall::Door;
+
+        let wall = Wall::new(
+            ( 0, 0).into(),
+            (10, 0).into(),
+            None,
+            Some((10, 0).into()),
+            Some(vec![ Door::new((2, 0).into(), (8, 0).into()) ])
+        );
+
+        let json: Json = wall.into();
+        let json = Json::parse(json.print().as_bytes()).unwrap();
+
+        let mut parsed = Wall::try_from(&json).unwrap();
+        assert_eq!(parsed.doors().len(), 1);
+
+        let patch = Json::parse(br#"{"position_end":{"x":20,"y":0},"doors":null}"#).unwrap();
+        parsed.patch(&patch).unwrap();
+
+        assert!(parsed.doors().is_empty());
+
+        let json: Json = parsed.into();
+        assert_eq!(
+            json.print(),
+            r#"{"position_start":{"x":0,"y":0},"position_end":{"x":20,"y":0},"connection_end":{"x":10,"y":0},"connection_start":null,"doors":null}"#
+        );
+    }
+
+    #[test]
+    fn house_json_round_trip() {
+        use json_minimal::Json;
+
+        use crate::house_layout::{floor::Floor, house::House, room::Room};
+
+        let mut house = House::default();
+        house.push_floor();
+        house.floor_mut(1).unwrap().add_room(Room::default());
+
+        let json = Json::JSON(vec![house.clone().into()]);
+        let printed = json.print();
+
+        let parsed = House::try_from(&Json::parse(printed.as_bytes()).unwrap()).unwrap();
+        assert_eq!(parsed.floors().len(), 2);
+        assert_eq!(parsed.floor(1).map(Floor::number), Some(1));
+        assert_eq!(Json::JSON(vec![parsed.into()]).print(), printed);
+
+        assert!(House::try_from(&Json::parse(br#"{"floors":[{"rooms":[]}]}"#).unwrap()).is_err());
+    }
+
+    #[test]
+    fn house_json_error_path() {
+        use json_minimal::Json;
+
+        use crate::{helpers::JsonError, house_layout::house::House};
+
+        let path = |json: &[u8]| {
+            let error = House::try_from(&Json::parse(json).unwrap()).unwrap_err();
+            error.downcast::<JsonError>().unwrap().path().to_string()
+        };
+
+        let wall = r#"{"position_start":{"x":0,"y":0},"position_end":{"x":1,"y":0}}"#;
+        let bad_wall = r#"{"position_start":{"x":"0","y":0},"position_end":{"x":1,"y":0}}"#;
+        let json = format!(
+            r#"{{"floors":[{{"number":0}},{{"number":1,"rooms":[{{"height":250,"walls":[{wall},{wall},{bad_wall}]}}]}}]}}"#
+        );
+
+        assert_eq!(path(json.as_bytes()), "/floors/1/rooms/0/walls/2/position_start/x");
+        assert_eq!(path(br#"{"floors":[{"number":0,"rooms":[{}]}]}"#), "/floors/0/rooms/0/height");
+        assert_eq!(path(br#"{}"#), "/floors");
+
+        // Координаты и высота - целые сантиметры от 0 до 65535
+        for bad in [r#"{"x":-5,"y":0}"#, r#"{"x":1.5,"y":0}"#, r#"{"x":70000,"y":0}"#] {
+            let json = format!(
+                r#"{{"floors":[{{"number":0,"rooms":[{{"height":250,"walls":[{wall},{wall},{{"position_start":{bad},"position_end":{{"x":1,"y":0}}}}]}}]}}]}}"#
+            );
+            assert_eq!(path(json.as_bytes()), "/floors/0/rooms/0/walls/2/position_start/x");
+        }
+        assert_eq!(path(br#"{"floors":[{"number":0,"rooms":[{"height":250.5}]}]}"#), "/floors/0/rooms/0/height");
+
+        // Повтор номера этажа находит только проверка дома целиком
+        let house = House::try_from(&Json::parse(br#"{"floors":[{"number":1},{"number":1}]}"#).unwrap()).unwrap();
+        let error = house.validate().unwrap_err().downcast::<JsonError>().unwrap();
+        assert_eq!(error.path(), "/floors/1/number");
+    }
+
+    #[test]
+    fn room_at_position() {
+        use crate::house_layout::{floor::Floor, room::Room};
+
+        let room = |points: Vec<(u16, u16)>| Room::new(
+            Wall::from_connections(points.into_iter().map(|p| p.into()).collect()),
+            250.into(),
+            vec![]
+        );
+
+        let floor = Floor::new(vec![
+            Room::default(),
+            room(vec![(0, 0), (400, 0), (400, 300), (0, 300)]),
+            room(vec![(400, 0), (700, 0), (700, 300), (400, 300)]),
+        ], 0);
+
+        assert_eq!(floor.room_at(&(100, 100).into()).map(|(idx, _)| idx), Some(1));
+        assert_eq!(floor.room_at(&(500, 299).into()).map(|(idx, _)| idx), Some(2));
+        assert!(floor.room_at(&(800, 100).into()).is_none());
+    }
+
+    #[test]
+    fn devices_in_house() {
+        use json_minimal::Json;
+
+        use crate::{helpers::JsonError, house_layout::house::House, types::GaugeJson};
+
+        let gauge = |json: &str| GaugeJson::try_from(&Json::parse(json.as_bytes()).unwrap());
+        let alarm = |device: &str| gauge(&format!(
+            r#"{{"id":"fire_alarm","name":"Living room alarm","state":"Enabled","device":"{device}"}}"#
+        ));
+
+        let mut house = House::default();
+        house.push_floor();
+        house.floor_mut(1).unwrap().add_room(Default::default());
+        house.floor_mut(1).unwrap().room_mut(0).unwrap().gauges.push(alarm("FA-0001").unwrap());
+        house.floor_mut(0).unwrap().room_mut(0).unwrap().gauges.push(alarm("FA-0002").unwrap());
+
+        assert_eq!(house.locate_device(&"FA-0001".parse().unwrap()), Some((1, 0, 1)));
+        assert_eq!(house.duplicate_device(), None);
+
+        let mut renamed = alarm("FA-0002").unwrap();
+        renamed.patch(&Json::parse(br#"{"name":"Hall alarm"}"#).unwrap()).unwrap();
+        assert_eq!(renamed.device(), Some("FA-0002".parse().unwrap()));
+        house.floor_mut(1).unwrap().room_mut(0).unwrap().gauges.push(renamed);
+        assert_eq!(house.duplicate_device(), Some("FA-0002".parse().unwrap()));
+
+        let error = alarm("not a device").unwrap_err().downcast::<JsonError>().unwrap();
+        assert_eq!(error.path(), "/device");
+        assert!(gauge(r#"{"id":"fire_alarm","name":"Hall","state":"Enabled","device":null}"#).unwrap().device().is_none());
+    }
+
     // #[test]
     // fn create_house() {
     //     let walls = 
@@ -1,8 +1,8 @@
 use json_minimal::Json;
 
-use crate::{helpers::{json_check_array, json_check_number}, types::{fire_alarm::{FireAlarm, FireAlarmState}, Gauge, GaugeJson}};
+use crate::{helpers::{json_check_array, json_required, JsonError}, types::{fire_alarm::{FireAlarm, FireAlarmState, FIRE_ALARM_ID}, Gauge, GaugeJson}};
 
-use super::{dimensions::Centimeters, wall::Wall};
+use super::{dimensions::{json_centimeters, Centimeters, Position}, geometry::Polygon, wall::Wall};
 
 #[derive(Clone, Debug)]
 pub struct Room {
@@ -52,8 +52,7 @@ impl TryFrom<&Json> for Room {
     /// `gauges`
     /// `walls`
     fn try_from(value: &Json) -> Result<Self, Self::Error> {
-        let height: Centimeters =
-            json_check_number(value, "height",Err("height field required".into()))?;
+        let height = json_centimeters(value, "height", json_required("height"))?;
         let walls: Vec<Wall> =
             json_check_array(value, "walls",Ok(vec![]))?;
         let gauges: Vec<GaugeJson> =
@@ -73,6 +72,87 @@ impl Room {
     pub fn new(walls: Vec<Wall>, height: Centimeters, gauges: Vec<GaugeJson>) -> Self {
         Self { walls, height, gauges }
     }
+
+    /// Многоугольник комнаты из ее стен (см. [`Polygon::from_walls`])
+    pub fn polygon(&self) -> Result<Polygon, super::geometry::GeometryError> {
+        Polygon::from_walls(&self.walls)
+    }
+
+    /// Есть ли в комнате сработавшая пожарная сигнализация
+    pub fn on_alert(&self) -> bool {
+        let alert = FireAlarmState::OnAlert.to_string();
+
+        self.gauges
+            .iter()
+            .any(|g| g.id().as_bytes() == FIRE_ALARM_ID && g.state() == alert)
+    }
+
+    /// Лежит ли `position` внутри комнаты или на ее стене
+    pub fn contains(&self, position: &Position) -> bool {
+        self.polygon().is_ok_and(|p| p.contains(position))
+    }
+
+    /// Комната без стен еще не размечена и считается корректной,
+    /// иначе стены должны образовывать корректный многоугольник.
+    /// Стена нулевой длины, как у [`Room::default`], - ошибка.
+    /// Двери должны лежать на своих стенах,
+    /// размещенные датчики должны находиться внутри комнаты
+    pub fn validate(&self) -> crate::Result<()> {
+        if !self.walls.is_empty() {
+            if let Err(e) = self.polygon() {
+                let error = match e.wall() {
+                    Some(wall) => JsonError::field(wall, e.to_string()).within("walls"),
+                    None => JsonError::field("walls", e.to_string()),
+                };
+
+                Err(error)?
+            }
+        }
+
+        for (idx, wall) in self.walls.iter().enumerate() {
+            wall.check_doors()
+                .map_err(|e| e.within(idx).within("walls"))?;
+        }
+
+        for (idx, gauge) in self.gauges.iter().enumerate() {
+            self.check_placement(gauge)
+                .map_err(|e| e.within(idx).within("gauges"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Датчик без `position` можно добавить в любую комнату,
+    /// иначе он должен находиться внутри нее
+    pub fn check_placement(&self, gauge: &GaugeJson) -> Result<(), JsonError> {
+        match gauge.position() {
+            Some(position) if !self.contains(&position) =>
+                Err(JsonError::field("position", "gauge is outside of the room")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Обновляет только указанные поля.
+    /// `walls` и `gauges` заменяются целиком
+    pub fn patch(&mut self, value: &Json) -> crate::Result<()> {
+        let height = json_centimeters(value, "height", Ok(self.height))?;
+        let walls: Option<Vec<Wall>> = value.get("walls")
+            .map(|_| json_check_array(value, "walls", Ok(vec![])))
+            .transpose()?;
+        let gauges: Option<Vec<GaugeJson>> = value.get("gauges")
+            .map(|_| json_check_array(value, "gauges", Ok(vec![])))
+            .transpose()?;
+
+        self.height = height;
+        if let Some(walls) = walls {
+            self.walls = walls;
+        }
+        if let Some(gauges) = gauges {
+            self.gauges = gauges;
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for Room {
@@ -0,0 +1,213 @@
+//! План этажа в SVG
+//!
+//! Координаты рисунка совпадают с координатами плана в сантиметрах.
+//! Стены рисуются линиями, двери - разрывами в стенах (выходы зеленым),
+//! в центре комнаты подпись с номером и площадью. Датчики с `position`
+//! рисуются кружками, цвет зависит от состояния, см. [`gauge_colour`].
+use std::fmt::Write;
+
+use crate::types::{fire_alarm::{FireAlarmState, FIRE_ALARM_ID}, temperature_gauge::{Temperature, TemperatureGaugeState, TEMPERATURE_GAUGE_ID}, GaugeJson};
+
+use super::{dimensions::Position, floor::Floor, geometry::{point, Point}};
+
+/// Отступ вокруг плана в сантиметрах
+const MARGIN: i64 = 50;
+const WALL_WIDTH: i64 = 10;
+const GAUGE_RADIUS: i64 = 15;
+
+const ROOM_FILL: &str = "#fafafa";
+const ROOM_ON_ALERT_FILL: &str = "#fdecea";
+const WALL_COLOUR: &str = "#212121";
+const EXIT_COLOUR: &str = "#2e7d32";
+
+const DISABLED_COLOUR: &str = "#9e9e9e";
+const ENABLED_COLOUR: &str = "#43a047";
+const ON_ALERT_COLOUR: &str = "#d32f2f";
+
+/// Температуры, которым соответствуют крайние цвета шкалы
+const COLD: Temperature = 10.0;
+const HOT: Temperature = 40.0;
+const COLD_RGB: (u8, u8, u8) = (0x1e, 0x88, 0xe5);
+const HOT_RGB: (u8, u8, u8) = (0xe5, 0x39, 0x35);
+
+pub fn render(floor: &Floor) -> String {
+    let mut svg = String::new();
+    let (width, height) = bounds(floor);
+
+    // Запись в String не завершается ошибкой
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" font-family="sans-serif">"#,
+        -MARGIN, -MARGIN, width + 2 * MARGIN, height + 2 * MARGIN
+    );
+    let _ = writeln!(svg, "<title>Floor {}</title>", floor.number());
+
+    for room in floor.rooms() {
+        let Ok(polygon) = room.polygon() else {
+            continue;
+        };
+
+        let points: Vec<String> = polygon.vertices()
+            .iter()
+            .map(|v| { let (x, y) = point(v); format!("{x},{y}") })
+            .collect();
+        let fill = if room.on_alert() { ROOM_ON_ALERT_FILL } else { ROOM_FILL };
+
+        let _ = writeln!(svg, r#"<polygon points="{}" fill="{fill}"/>"#, points.join(" "));
+    }
+
+    for wall in floor.rooms().iter().flat_map(|r| &r.walls) {
+        line(&mut svg, point(wall.position_start()), point(wall.position_end()), WALL_COLOUR, WALL_WIDTH);
+    }
+
+    // Проем закрашивается цветом пола поверх стены
+    for door in floor.rooms().iter().flat_map(|r| &r.walls).flat_map(|w| w.doors()) {
+        let (start, end) = (point(door.position_start()), point(door.position_end()));
+
+        line(&mut svg, start, end, ROOM_FILL, WALL_WIDTH + 2);
+        if door.is_exit() {
+            line(&mut svg, start, end, EXIT_COLOUR, WALL_WIDTH / 2);
+        }
+    }
+
+    for (idx, room) in floor.rooms().iter().enumerate() {
+        let Ok(polygon) = room.polygon() else {
+            continue;
+        };
+        let (x, y) = point(&polygon.centroid());
+
+        let _ = writeln!(
+            svg,
+            r#"<text x="{x}" y="{y}" text-anchor="middle" font-size="24">Room {idx}<tspan x="{x}" dy="28" font-size="18">{:.1} m²</tspan></text>"#,
+            polygon.area()
+        );
+    }
+
+    for gauge in floor.rooms().iter().flat_map(|r| &r.gauges) {
+        let Some(position) = gauge.position() else {
+            continue;
+        };
+        let (x, y) = point(&position);
+
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{x}" cy="{y}" r="{GAUGE_RADIUS}" fill="{}" stroke="{WALL_COLOUR}" stroke-width="2"><title>{}: {}</title></circle>"#,
+            gauge_colour(gauge),
+            escape(gauge.name()),
+            escape(gauge.state())
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Цвет датчика по его состоянию: красный для сработавшей сигнализации,
+/// для термометра - от синего при [`COLD`] до красного при [`HOT`]
+pub fn gauge_colour(gauge: &GaugeJson) -> String {
+    let id = gauge.id().as_bytes();
+
+    if id == FIRE_ALARM_ID {
+        let colour = match gauge.state() {
+            s if s == FireAlarmState::OnAlert.to_string() => ON_ALERT_COLOUR,
+            s if s == FireAlarmState::Enabled.to_string() => ENABLED_COLOUR,
+            _ => DISABLED_COLOUR,
+        };
+
+        return colour.to_string();
+    }
+
+    if id == TEMPERATURE_GAUGE_ID {
+        return match gauge.state().parse() {
+            Ok(TemperatureGaugeState::ReadedTemperarure(t)) => gradient(t),
+            Ok(TemperatureGaugeState::Enabled) => ENABLED_COLOUR.to_string(),
+            _ => DISABLED_COLOUR.to_string(),
+        };
+    }
+
+    DISABLED_COLOUR.to_string()
+}
+
+fn gradient(temperature: Temperature) -> String {
+    let t = ((temperature - COLD) / (HOT - COLD)).clamp(0.0, 1.0);
+    let mix = |cold: u8, hot: u8| (cold as f32 + (hot as f32 - cold as f32) * t).round() as u8;
+
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        mix(COLD_RGB.0, HOT_RGB.0),
+        mix(COLD_RGB.1, HOT_RGB.1),
+        mix(COLD_RGB.2, HOT_RGB.2)
+    )
+}
+
+/// Правый нижний угол плана
+fn bounds(floor: &Floor) -> Point {
+    floor.rooms()
+        .iter()
+        .flat_map(|r| r.walls
+            .iter()
+            .flat_map(|w| [w.position_start().clone(), w.position_end().clone()])
+            .chain(r.gauges.iter().filter_map(GaugeJson::position))
+        )
+        .map(|p: Position| point(&p))
+        .fold((0, 0), |(w, h), (x, y)| (w.max(x), h.max(y)))
+}
+
+fn line(svg: &mut String, a: Point, b: Point, colour: &str, width: i64) {
+    let _ = writeln!(
+        svg,
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{colour}" stroke-width="{width}" stroke-linecap="square"/>"#,
+        a.0, a.1, b.0, b.1
+    );
+}
+
+fn escape(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{house_layout::{floor::Floor, room::Room, wall::{Door, Wall}}, types::{fire_alarm::{FireAlarm, FireAlarmState}, temperature_gauge::{TemperatureGauge, TemperatureGaugeState}, Gauge, GaugeJson}};
+
+    use super::{gauge_colour, render};
+
+    #[test]
+    fn gauge_colours() {
+        let alarm = FireAlarm::new("Hall".to_string(), FireAlarmState::OnAlert);
+        let cold = TemperatureGauge::new("Cold".to_string(), TemperatureGaugeState::ReadedTemperarure(-5.0));
+        let warm = TemperatureGauge::new("Warm".to_string(), TemperatureGaugeState::ReadedTemperarure(25.0));
+
+        assert_eq!(gauge_colour(&GaugeJson::new(&alarm.id(), alarm.name(), alarm.state())), "#d32f2f");
+        assert_eq!(gauge_colour(&GaugeJson::new(&cold.id(), cold.name(), cold.state())), "#1e88e5");
+        assert_eq!(gauge_colour(&GaugeJson::new(&warm.id(), warm.name(), warm.state())), "#82618d");
+    }
+
+    #[test]
+    fn floor_plan() {
+        let mut room = Room::new(
+            Wall::from_connections(vec![(0, 0).into(), (400, 0).into(), (400, 300).into(), (0, 300).into()]),
+            250.into(),
+            vec![]
+        );
+        room.walls[3].doors_mut().push(Door::new((0, 200).into(), (0, 100).into()).exit());
+
+        let alarm = FireAlarm::new("<Hall>".to_string(), FireAlarmState::OnAlert);
+        let mut gauge = GaugeJson::new(&alarm.id(), alarm.name(), alarm.state());
+        gauge.patch(&json_minimal::Json::parse(br#"{"position":{"x":200,"y":50}}"#).unwrap()).unwrap();
+        room.gauges.push(gauge);
+
+        let svg = render(&Floor::new(vec![room], 1));
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-50 -50 500 400""#));
+        assert!(svg.contains(r##"<polygon points="0,0 400,0 400,300 0,300" fill="#fdecea"/>"##));
+        assert!(svg.contains(r##"<line x1="0" y1="200" x2="0" y2="100" stroke="#2e7d32""##));
+        assert!(svg.contains(">Room 0<tspan"));
+        assert!(svg.contains(r##"<circle cx="200" cy="50" r="15" fill="#d32f2f""##));
+        assert!(svg.contains("<title>&lt;Hall&gt;: On alert</title>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
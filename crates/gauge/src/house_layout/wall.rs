@@ -1,24 +1,62 @@
 use json_minimal::Json;
 
-use crate::helpers::{json_check_array, json_check_number, json_check_object};
+use crate::helpers::{json_check_array, json_check_bool, json_check_field, json_nested, json_required, JsonError};
 
-use super::{dimensions::Position, Connection};
+use super::{dimensions::Position, geometry::{coordinates, length, projection}, Connection};
 
 #[derive(Debug, Clone)]
 pub struct Door {
     position_start: Position,
     position_end:   Position,
+
+    /// Эвакуационный выход с этажа
+    exit: bool,
 }
 
 impl Door {
-    pub fn new(position_start: Position, position_end: Position) -> Self { Self { position_start, position_end } }
+    pub fn new(position_start: Position, position_end: Position) -> Self { Self { position_start, position_end, exit: false } }
+
+    /// Помечает дверь как эвакуационный выход
+    pub fn exit(self) -> Self {
+        Self { exit: true, ..self }
+    }
+
+    pub fn is_exit(&self) -> bool {
+        self.exit
+    }
+
+    pub fn position_start(&self) -> &Position {
+        &self.position_start
+    }
+
+    pub fn position_end(&self) -> &Position {
+        &self.position_end
+    }
+
+    /// Обновляет `position_start`, `position_end` и `exit`, если они указаны
+    pub fn patch(&mut self, value: &Json) -> crate::Result<()> {
+        let value = value.unbox();
+
+        let position_start: Position =
+            json_check_field(value, "position_start", Ok(self.position_start.clone()))?;
+        let position_end: Position =
+            json_check_field(value, "position_end", Ok(self.position_end.clone()))?;
+        let exit = json_check_bool(value, "exit", Ok(self.exit))?;
+
+        self.position_start = position_start;
+        self.position_end = position_end;
+        self.exit = exit;
+
+        Ok(())
+    }
 }
 
 impl Into<Json> for Door {
     fn into(self) -> Json {
         Json::JSON(vec![
             Json::OBJECT { name: "position_start".into(), value: Box::new(self.position_start.into()) },
-            Json::OBJECT { name: "position_end".into(), value: Box::new(self.position_end.into()) }
+            Json::OBJECT { name: "position_end".into(), value: Box::new(self.position_end.into()) },
+            Json::OBJECT { name: "exit".into(), value: Box::new(Json::BOOL(self.exit)) }
         ])
     }
 }
@@ -26,23 +64,21 @@ impl Into<Json> for Door {
 impl TryFrom<&Json> for Door {
     type Error = crate::Error;
 
+    /// Requered fields:
+    /// `position_start`
+    /// `position_end`
+    /// Additional fields:
+    /// `exit`
     fn try_from(value: &Json) -> Result<Self, Self::Error> {
-        const NO_POSITION: &str = "postion star and end required";
+        let value = value.unbox();
 
-        let value = json_check_object(value)?;
+        let position_start: Position =
+            json_check_field(value, "position_start", json_required("position_start"))?;
+        let position_end: Position =
+            json_check_field(value, "position_end", json_required("position_end"))?;
+        let exit = json_check_bool(value, "exit", Ok(false))?;
 
-        let position_start: Position = value
-            .get("position_start")
-            .ok_or(NO_POSITION)?
-            .try_into()?
-        ;
-        let position_end: Position = value
-            .get("position_end")
-            .ok_or(NO_POSITION)?
-            .try_into()?
-        ;
-
-        Ok( Self { position_start, position_end })
+        Ok( Self { position_start, position_end, exit })
     }
 }
 
@@ -111,35 +147,17 @@ impl TryFrom<&Json> for Wall {
     type Error = crate::Error;
 
     fn try_from(value: &Json) -> Result<Self, Self::Error> {
-        const POSTION_REQUIRED: &str = "postion start and end required";
-
-        let position_start: Position = value.get("postition_start")
-            .ok_or(POSTION_REQUIRED)?
-            .try_into()?
-        ;
-
-        let position_end: Position = value.get("postition_end")
-            .ok_or(POSTION_REQUIRED)?
-            .try_into()?
-        ;
+        let position_start: Position =
+            json_check_field(value, "position_start", json_required("position_start"))?;
+        let position_end: Position =
+            json_check_field(value, "position_end", json_required("position_end"))?;
 
         let connection_start: Option<Connection> =
-        match value.get("connection_start") {
-            Some(v) => Some(v.try_into()?),
-            None => None,
-        };
-
+            json_connection(value, "connection_start")?.flatten();
         let connection_end: Option<Connection> =
-        match value.get("connection_end") {
-            Some(v) => Some(v.try_into()?),
-            None => None,
-        };
+            json_connection(value, "connection_end")?.flatten();
 
-        let doors: Vec<Door> =
-        json_check_array(value, "doors", Ok(vec![]))?;
-        let doors = doors.is_empty()
-            .then_some(doors)
-        ;
+        let doors: Option<Vec<Door>> = json_doors(value)?.flatten();
 
         Ok(
             Self
@@ -154,6 +172,30 @@ impl TryFrom<&Json> for Wall {
     }
 }
 
+/// `None`, если поле `field` не указано, `Some(None)`, если оно `null`
+fn json_connection(value: &Json, field: &str) -> crate::Result<Option<Option<Connection>>> {
+    match value.get(field) {
+        None => Ok(None),
+        Some(v) if matches!(v.unbox(), Json::NULL) => Ok(Some(None)),
+        Some(v) => Ok(Some(Some(
+            v.try_into().map_err(|e| json_nested(e, field))?
+        ))),
+    }
+}
+
+/// `None`, если поле `doors` не указано, `Some(None)`, если оно `null` или пустое
+fn json_doors(value: &Json) -> crate::Result<Option<Option<Vec<Door>>>> {
+    match value.get("doors").map(|v| v.unbox()) {
+        None => Ok(None),
+        Some(Json::NULL) => Ok(Some(None)),
+        Some(_) => {
+            let doors: Vec<Door> = json_check_array(value, "doors", Ok(vec![]))?;
+
+            Ok(Some((!doors.is_empty()).then_some(doors)))
+        },
+    }
+}
+
 impl Default for Wall {
     fn default() -> Self {
         Self {
@@ -183,6 +225,98 @@ impl Wall {
         }
     }
 
+    pub fn position_start(&self) -> &Position {
+        &self.position_start
+    }
+
+    pub fn position_end(&self) -> &Position {
+        &self.position_end
+    }
+
+    /// Отвергает стены нулевой длины и двери не на стене
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.position_start == self.position_end {
+            Err(JsonError::field("position_end", "wall has zero length"))?
+        }
+
+        self.check_doors()?;
+
+        Ok(())
+    }
+
+    /// Проверяет каждую дверь стены (см. [`Wall::check_door`])
+    pub fn check_doors(&self) -> Result<(), JsonError> {
+        for (idx, door) in self.doors().iter().enumerate() {
+            self.check_door(door)
+                .map_err(|e| e.within(idx).within("doors"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Оба конца двери должны лежать на стене
+    pub fn check_door(&self, door: &Door) -> Result<(), JsonError> {
+        if door.position_start() == door.position_end() {
+            return Err(JsonError::field("position_end", "door has zero width"));
+        }
+
+        for (field, end) in [("position_start", door.position_start()), ("position_end", door.position_end())] {
+            let on_wall = projection(self, coordinates(end))
+                .is_some_and(|t| (0.0..=length(self)).contains(&t));
+
+            if !on_wall {
+                return Err(JsonError::field(field, "door is not on the wall"));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn doors(&self) -> &[Door] {
+        self.doors.as_deref().unwrap_or_default()
+    }
+
+    /// Дверь `idx`, пустой список дверей при этом не создается
+    pub fn door_mut(&mut self, idx: usize) -> Option<&mut Door> {
+        self.doors.as_mut()?.get_mut(idx)
+    }
+
+    pub fn doors_mut(&mut self) -> &mut Vec<Door> {
+        self.doors.get_or_insert_with(Vec::new)
+    }
+
+    /// Убирает пустой список дверей, чтобы он сериализовался как `null`
+    pub fn remove_door(&mut self, idx: usize) -> Option<Door> {
+        let doors = self.doors.as_mut()?;
+        let door = (idx < doors.len()).then(|| doors.remove(idx));
+
+        if doors.is_empty() {
+            self.doors = None;
+        }
+
+        door
+    }
+
+    /// Обновляет только указанные поля
+    pub fn patch(&mut self, value: &Json) -> crate::Result<()> {
+        let position_start: Position =
+            json_check_field(value, "position_start", Ok(self.position_start.clone()))?;
+        let position_end: Position =
+            json_check_field(value, "position_end", Ok(self.position_end.clone()))?;
+        let connection_start: Option<Connection> = json_connection(value, "connection_start")?
+            .unwrap_or_else(|| self.connection_start.clone());
+        let connection_end: Option<Connection> = json_connection(value, "connection_end")?
+            .unwrap_or_else(|| self.connection_end.clone());
+        let doors = match json_doors(value)? {
+            Some(doors) => doors,
+            None => self.doors.clone(),
+        };
+
+        *self = Self::new(position_start, position_end, connection_start, connection_end, doors);
+
+        Ok(())
+    }
+
     pub fn from_connections(connections: Vec<Connection>) -> Vec<Wall> {
         let mut walls: Vec<Wall>  = Vec::new();
 
@@ -0,0 +1,134 @@
+//! Соединение датчика с сервером
+//!
+//! [`GaugeClient`] подключается к серверу при первой отправке,
+//! проходит рукопожатие [`handshake`] и держит соединение открытым.
+//! Если сервер поддерживает команды, отдельный поток читает их,
+//! передает обработчику и отвечает серверу кадром [`Ack`].
+//! Если соединение разорвано, следующая отправка подключится заново.
+use std::{io::Write, net::{Shutdown, TcpStream, ToSocketAddrs}, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex, MutexGuard}, thread, time::Duration};
+
+use super::{command::{Ack, Command}, frame::FrameDecoder, handshake::{self, Hello, FEATURE_COMMANDS}, SerializedCommand};
+
+/// Сколько ждать подключения и ответа на приветствие,
+/// пока другие отправки ждут соединение
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Выполняет команду сервера, `Err` - причина отказа для [`Ack`]
+type CommandHandler = dyn Fn(SerializedCommand, &GaugeClient) -> Result<(), String> + Send + Sync;
+
+struct Connection {
+    address: String,
+    hello: Hello,
+    stream: Mutex<Option<TcpStream>>,
+    /// Номер текущего соединения, чтобы поток старого соединения
+    /// не закрыл новое
+    generation: AtomicU64,
+    handler: Box<CommandHandler>,
+}
+
+#[derive(Clone)]
+pub struct GaugeClient(Arc<Connection>);
+
+impl GaugeClient {
+    pub fn new(
+        address: String,
+        hello: Hello,
+        handler: impl Fn(SerializedCommand, &GaugeClient) -> Result<(), String> + Send + Sync + 'static
+    ) -> Self {
+        Self(Arc::new(Connection {
+            address,
+            hello,
+            stream: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            handler: Box::new(handler),
+        }))
+    }
+
+    /// Подключается, если соединения еще нет
+    pub fn connect(&self) -> crate::Result<()> {
+        let mut stream = self.stream();
+        if stream.is_some() {
+            return Ok(());
+        }
+
+        let address = self.0.address.to_socket_addrs()?.next().ok_or("Address not resolved")?;
+        let mut connection = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        connection.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        let (_, features) = handshake::greet(&mut connection, &self.0.hello)?;
+        // Команды сервера могут приходить сколь угодно редко
+        connection.set_read_timeout(None)?;
+
+        let generation = self.0.generation.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if features.iter().any(|f| f == FEATURE_COMMANDS) {
+            let reader = connection.try_clone()?;
+            let client = self.clone();
+            thread::spawn(move || client.listen(reader, generation));
+        }
+
+        *stream = Some(connection);
+
+        Ok(())
+    }
+
+    /// Отправляет кадр, например [`Gauge::serialize`](super::Gauge::serialize).
+    /// После ошибки соединение закрывается
+    pub fn send(&self, frame: &[u8]) -> crate::Result<()> {
+        self.connect()?;
+
+        let mut stream = self.stream();
+        let Some(connection) = stream.as_mut() else {
+            return Err("Connection closed".into());
+        };
+
+        if let Err(e) = connection.write_all(frame) {
+            // Поток чтения держит копию сокета, поэтому сокет закрывается явно
+            let _ = connection.shutdown(Shutdown::Both);
+            *stream = None;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    fn listen(&self, mut reader: TcpStream, generation: u64) {
+        let mut decoder = FrameDecoder::new();
+
+        loop {
+            let frame = match decoder.read_frame(&mut reader) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("Error reading command: {e}");
+                    break;
+                },
+            };
+
+            let command = match Command::parse(frame) {
+                Ok(command) => command,
+                Err(e) => {
+                    eprintln!("Malformed command: {e}");
+                    continue;
+                },
+            };
+
+            let ack = Ack { seq: command.seq, result: (self.0.handler)(command.command, self) };
+            if let Err(e) = ack.encode().and_then(|ack| self.send(&ack)) {
+                eprintln!("Error sending command acknowledgement: {e}");
+            }
+        }
+
+        // Сервер закрыл соединение, следующая отправка подключится заново.
+        // Если `send` уже переподключился, новое соединение не трогаем
+        let mut stream = self.stream();
+        if self.0.generation.load(Ordering::Relaxed) == generation {
+            *stream = None;
+        }
+    }
+
+    fn stream(&self) -> MutexGuard<'_, Option<TcpStream>> {
+        self.0.stream
+            .lock()
+            .expect("Error locking mutex")
+    }
+}
@@ -0,0 +1,125 @@
+//! Команды сервера датчику и ответы на них
+//!
+//! Сервер отправляет кадр [`Command`] с номером и сериализованной
+//! командой ([`GaugeCommand::serialize_command`](super::GaugeCommand::serialize_command)).
+//! Датчик выполняет команду и отвечает кадром [`Ack`] с тем же номером.
+//! Состояние датчик продолжает присылать в том же соединении, поэтому
+//! сервер разбирает входящие кадры через [`Message`].
+use super::{frame::{self, Fields}, DeserializedGauge, SerializedCommand, SerializedGauge};
+
+const OK: &[u8] = b"ok";
+const FAILED: &[u8] = b"failed";
+
+/// Номер команды, по которому сервер сопоставляет ответ
+pub type Sequence = u32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub seq: Sequence,
+    pub command: SerializedCommand,
+}
+
+impl Command {
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        Ok(frame::encode(&[
+            ("command", &self.seq.to_be_bytes()),
+            ("code", &self.command),
+        ])?)
+    }
+
+    pub fn parse(gauge: SerializedGauge) -> crate::Result<Self> {
+        let (fields, _) = frame::decode(&gauge.0)?;
+
+        Ok(Self {
+            seq: sequence(&fields, "command")?,
+            command: fields.get("code").ok_or("Command code not found")?.clone(),
+        })
+    }
+}
+
+/// Ответ датчика на [`Command`], `Err` - причина, по которой команда не выполнена
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ack {
+    pub seq: Sequence,
+    pub result: Result<(), String>,
+}
+
+impl Ack {
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        let frame = match &self.result {
+            Ok(()) => frame::encode(&[
+                ("ack", &self.seq.to_be_bytes()),
+                ("status", OK),
+            ]),
+            Err(reason) => frame::encode(&[
+                ("ack", &self.seq.to_be_bytes()),
+                ("status", FAILED),
+                ("reason", reason.as_bytes()),
+            ]),
+        };
+
+        Ok(frame?)
+    }
+
+    fn from_fields(fields: &Fields) -> crate::Result<Self> {
+        let result = match fields.get("status").map(|s| s.as_slice()) {
+            Some(OK) => Ok(()),
+            Some(FAILED) => Err(
+                String::from_utf8(fields.get("reason").cloned().unwrap_or_default())?
+            ),
+            _ => Err("Ack status not found")?,
+        };
+
+        Ok(Self { seq: sequence(fields, "ack")?, result })
+    }
+}
+
+/// Кадр, который датчик присылает серверу после рукопожатия
+#[derive(Debug)]
+pub enum Message {
+    State(DeserializedGauge),
+    Ack(Ack),
+}
+
+impl Message {
+    pub fn parse(gauge: SerializedGauge) -> crate::Result<Self> {
+        let (fields, _) = frame::decode(&gauge.0)?;
+
+        match fields.contains_key("ack") {
+            true => Ok(Message::Ack(Ack::from_fields(&fields)?)),
+            false => Ok(Message::State(DeserializedGauge(fields))),
+        }
+    }
+}
+
+fn sequence(fields: &Fields, field: &str) -> crate::Result<Sequence> {
+    let bytes: [u8; 4] = fields
+        .get(field)
+        .ok_or("Command sequence not found")?
+        .as_slice()
+        .try_into()?;
+
+    Ok(Sequence::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{fire_alarm::{FireAlarm, FireAlarmCommand, FireAlarmState}, Gauge, GaugeCommand};
+
+    use super::{Ack, Command, Message};
+
+    #[test]
+    fn command_and_replies() {
+        let command = Command { seq: 7, command: FireAlarmCommand::Silence.serialize_command() };
+        let parsed = Command::parse(command.encode().unwrap().into()).unwrap();
+        assert_eq!(FireAlarmCommand::parse_command(parsed.command).unwrap(), FireAlarmCommand::Silence);
+
+        let ack = Ack { seq: 7, result: Err("alarm is not sounding".to_string()) };
+        assert!(matches!(Message::parse(ack.encode().unwrap().into()).unwrap(), Message::Ack(parsed) if parsed == ack));
+
+        let state = FireAlarm::new("Hall".to_string(), FireAlarmState::Enabled).serialize().unwrap();
+        assert!(matches!(Message::parse(state.into()).unwrap(), Message::State(_)));
+
+        assert!(Command::parse(ack.encode().unwrap().into()).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Идентификатор устройства
+//!
+//! [`GaugeIdentifier`](super::GaugeIdentifier) - это тип датчика, а имя
+//! может повторяться и меняться. [`DeviceId`] создается один раз при первом
+//! запуске устройства, хранится в файле и передается при рукопожатии
+//! и в каждом кадре состояния.
+use std::{collections::hash_map::RandomState, fmt::Display, fs, hash::{BuildHasher, Hasher}, io, path::Path, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
+
+/// Самый длинный допустимый идентификатор
+pub const MAX_DEVICE_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceId(String);
+
+impl DeviceId {
+    /// Случайный идентификатор в виде UUID версии 4
+    pub fn generate() -> Self {
+        let mut bytes = [random(), random()]
+            .map(u64::to_be_bytes)
+            .concat();
+
+        bytes[6] = (bytes[6] & 0x0F) | 0x40;
+        bytes[8] = (bytes[8] & 0x3F) | 0x80;
+
+        let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+        Self(format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]))
+    }
+
+    /// Читает идентификатор из `path`, а если файла нет,
+    /// создает новый и сохраняет его туда
+    pub fn load_or_create(path: impl AsRef<Path>) -> crate::Result<Self> {
+        match fs::read_to_string(&path) {
+            Ok(id) => id.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let id = Self::generate();
+                fs::write(&path, format!("{id}\n"))?;
+
+                Ok(id)
+            },
+            Err(e) => Err(e)?,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for DeviceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Латинские буквы, цифры, `-`, `_`, `:` и `.`, например UUID или MAC-адрес
+impl FromStr for DeviceId {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s.trim();
+
+        if id.is_empty() || id.len() > MAX_DEVICE_ID_LEN {
+            Err(format!("device id must be 1 to {MAX_DEVICE_ID_LEN} characters long"))?
+        }
+
+        if !id.chars().all(|c| c.is_ascii_alphanumeric() || "-_:.".contains(c)) {
+            Err("device id may contain only latin letters, digits, '-', '_', ':' and '.'")?
+        }
+
+        Ok(Self(id.to_string()))
+    }
+}
+
+/// Каждый [`RandomState`] получает свои случайные ключи
+fn random() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default()
+    );
+    hasher.write_u32(std::process::id());
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeviceId;
+
+    #[test]
+    fn generate_and_persist() {
+        let id = DeviceId::generate();
+        assert_eq!(id.as_str().len(), 36);
+        assert_eq!(&id.as_str()[14..15], "4");
+        assert_ne!(id, DeviceId::generate());
+        assert_eq!(id.as_str().parse::<DeviceId>().unwrap(), id);
+
+        let path = std::env::temp_dir().join(format!("device_id_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let created = DeviceId::load_or_create(&path).unwrap();
+        assert_eq!(DeviceId::load_or_create(&path).unwrap(), created);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!("AA:BB:CC:00:11:22".parse::<DeviceId>().unwrap().as_str(), "AA:BB:CC:00:11:22");
+        assert!(" ".parse::<DeviceId>().is_err());
+        assert!("Living room alarm".parse::<DeviceId>().is_err());
+    }
+}
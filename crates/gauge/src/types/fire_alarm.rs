@@ -1,6 +1,6 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-use super::{Gauge, GaugeName, GaugeState};
+use super::{device::DeviceId, Gauge, GaugeCommand, GaugeName, GaugeState};
 
 pub const FIRE_ALARM_ID: &[u8] = "fire_alarm".as_bytes();
 
@@ -25,10 +25,10 @@ impl Display for FireAlarmState {
 
 impl GaugeState for FireAlarmState {
     fn parse_state(state: super::SerializedState) -> crate::Result<Self> {
-        let state = match state[0] {
-            0 => FireAlarmState::Disabled,
-            1 => FireAlarmState::Enabled,
-            2 => FireAlarmState::OnAlert,
+        let state = match state.first() {
+            Some(0) => FireAlarmState::Disabled,
+            Some(1) => FireAlarmState::Enabled,
+            Some(2) => FireAlarmState::OnAlert,
             _ => Err("Error deserializing state")?
         };
 
@@ -36,10 +36,74 @@ impl GaugeState for FireAlarmState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FireAlarmCommand {
+    Enable,
+    Disable,
+    /// Выключить сработавшую сигнализацию, не отключая датчик
+    Silence,
+    SelfTest,
+}
+
+impl Display for FireAlarmCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let command = match self {
+            FireAlarmCommand::Enable => "enable",
+            FireAlarmCommand::Disable => "disable",
+            FireAlarmCommand::Silence => "silence",
+            FireAlarmCommand::SelfTest => "self-test",
+        };
+
+        write!(f,"{}",command)
+    }
+}
+
+impl FromStr for FireAlarmCommand {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let command = match s.trim() {
+            "enable" => FireAlarmCommand::Enable,
+            "disable" => FireAlarmCommand::Disable,
+            "silence" => FireAlarmCommand::Silence,
+            "self-test" => FireAlarmCommand::SelfTest,
+            _ => Err("Unknown Fire Alarm command, expected enable, disable, silence or self-test")?
+        };
+
+        Ok(command)
+    }
+}
+
+impl GaugeCommand for FireAlarmCommand {
+    fn parse_command(command: super::SerializedCommand) -> crate::Result<Self> {
+        let command = match command.as_slice() {
+            [0] => FireAlarmCommand::Enable,
+            [1] => FireAlarmCommand::Disable,
+            [2] => FireAlarmCommand::Silence,
+            [3] => FireAlarmCommand::SelfTest,
+            _ => Err("Error deserializing command")?
+        };
+
+        Ok(command)
+    }
+
+    fn serialize_command(&self) -> super::SerializedCommand {
+        let code = match self {
+            FireAlarmCommand::Enable => 0,
+            FireAlarmCommand::Disable => 1,
+            FireAlarmCommand::Silence => 2,
+            FireAlarmCommand::SelfTest => 3,
+        };
+
+        vec![code]
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct FireAlarm {
     name: GaugeName,
-    state: FireAlarmState
+    state: FireAlarmState,
+    device: Option<DeviceId>,
 }
 
 impl Display for FireAlarm {
@@ -63,9 +127,10 @@ impl Display for FireAlarm {
 
 impl Gauge for FireAlarm {
     type GaugeState = FireAlarmState;
+    type GaugeCommand = FireAlarmCommand;
 
     fn new(name: GaugeName, state: Self::GaugeState) -> Self {
-        Self { name, state }
+        Self { name, state, device: None }
     }
 
     fn serialize_id(&self) -> super::SerializedGaugeId {
@@ -94,6 +159,14 @@ impl Gauge for FireAlarm {
         &self.name
     }
 
+    fn device(&self) -> Option<&DeviceId> {
+        self.device.as_ref()
+    }
+
+    fn set_device(&mut self, device: DeviceId) {
+        self.device = Some(device)
+    }
+
     fn id(&self) -> super::GaugeIdentifier {
         FIRE_ALARM_ID.to_vec()
     }
@@ -109,15 +182,17 @@ impl Gauge for FireAlarm {
 
 #[cfg(test)]
 mod test {
-    use crate::types::Gauge;
+    use crate::types::{Gauge, GaugeCommand};
 
-    use super::{FireAlarm, FireAlarmState};
+    use crate::types::device::DeviceId;
+
+    use super::{FireAlarm, FireAlarmCommand, FireAlarmState};
 
     #[test]
     fn serialize_and_deserialize() {
         let fire_alarm: FireAlarm = FireAlarm::new("Room".to_string(),FireAlarmState::Enabled);
 
-        let serialized = fire_alarm.serialize();
+        let serialized = fire_alarm.serialize().unwrap();
 
         let deserialized = FireAlarm::deserialize(serialized.into())
             .expect("Deserialization error");
@@ -125,4 +200,34 @@ mod test {
         assert_eq!(fire_alarm,deserialized)
 
     }
+
+    #[test]
+    fn device_in_frame() {
+        let mut fire_alarm = FireAlarm::new("Living room alarm".to_string(), FireAlarmState::Enabled);
+        let device: DeviceId = "FA-0001".parse().unwrap();
+        fire_alarm.set_device(device.clone());
+
+        let deserialized = FireAlarm::deserialize(fire_alarm.serialize().unwrap().into()).unwrap();
+        assert_eq!(deserialized.device(), Some(&device));
+        assert_eq!(deserialized, fire_alarm);
+
+        // Такое же имя, но другое устройство
+        let mut twin = FireAlarm::new("Living room alarm".to_string(), FireAlarmState::Enabled);
+        twin.set_device("FA-0002".parse().unwrap());
+        assert_ne!(twin, fire_alarm);
+
+        let malformed = crate::types::frame::encode(&[("name", b"Hall"), ("state", &[1]), ("device", b"no spaces")]).unwrap();
+        assert!(FireAlarm::deserialize(malformed.into()).is_err());
+    }
+
+    #[test]
+    fn commands() {
+        for command in [FireAlarmCommand::Enable, FireAlarmCommand::Disable, FireAlarmCommand::Silence, FireAlarmCommand::SelfTest] {
+            assert_eq!(FireAlarmCommand::parse_command(command.serialize_command()).unwrap(), command);
+            assert_eq!(command.to_string().parse::<FireAlarmCommand>().unwrap(), command);
+        }
+
+        assert!(FireAlarmCommand::parse_command(vec![]).is_err());
+        assert!("interval 5".parse::<FireAlarmCommand>().is_err());
+    }
 }
@@ -0,0 +1,307 @@
+//! Кадр протокола датчиков
+//!
+//! ```text
+//! | длина полезной нагрузки: u32 BE | поля ... | CRC-32 полезной нагрузки: u32 BE |
+//! ```
+//!
+//! Каждое поле записывается как длина ключа (`u8`), ключ в UTF-8,
+//! длина значения (`u16` BE) и само значение. Значение - произвольные
+//! байты, разделителей в кадре нет, поэтому никакое значение не может
+//! его испортить.
+//!
+//! Из потока байт кадры достает [`FrameDecoder`].
+use std::{collections::HashMap, fmt::Display, io::Read};
+
+use super::SerializedGauge;
+
+/// Размер заголовка с длиной полезной нагрузки
+pub const HEADER_LEN: usize = 4;
+/// Размер контрольной суммы в конце кадра
+pub const CHECKSUM_LEN: usize = 4;
+/// Самая длинная полезная нагрузка, которую принимает [`decode`]
+pub const MAX_PAYLOAD_LEN: usize = 16 * 1024;
+
+pub type Fields = HashMap<String, Vec<u8>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// Кадр еще не пришел целиком, нужно `needed` байт всего
+    Incomplete { needed: usize },
+    /// Заголовок обещает полезную нагрузку длиннее [`MAX_PAYLOAD_LEN`]
+    TooLong(usize),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    /// Поле выходит за пределы полезной нагрузки
+    TruncatedField { offset: usize },
+    InvalidKey { offset: usize },
+    DuplicateField(String),
+    /// Ключ или значение не помещаются в поле при кодировании
+    FieldTooLong(String),
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Incomplete { needed } =>
+                write!(f, "incomplete frame, {needed} bytes needed"),
+            FrameError::TooLong(len) =>
+                write!(f, "frame payload of {len} bytes exceeds {MAX_PAYLOAD_LEN}"),
+            FrameError::ChecksumMismatch { expected, actual } =>
+                write!(f, "frame checksum mismatch: expected {expected:08x}, got {actual:08x}"),
+            FrameError::TruncatedField { offset } =>
+                write!(f, "field at {offset} runs past the end of the frame"),
+            FrameError::InvalidKey { offset } =>
+                write!(f, "field key at {offset} is not valid utf-8"),
+            FrameError::DuplicateField(key) =>
+                write!(f, "duplicate field '{key}'"),
+            FrameError::FieldTooLong(key) =>
+                write!(f, "field '{key}' is too long for a frame"),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Собирает кадр из полей в заданном порядке
+pub fn encode(fields: &[(&str, &[u8])]) -> Result<Vec<u8>, FrameError> {
+    let mut payload = vec![];
+
+    for (key, value) in fields {
+        let key_len = u8::try_from(key.len())
+            .map_err(|_| FrameError::FieldTooLong(key.to_string()))?;
+        let value_len = u16::try_from(value.len())
+            .map_err(|_| FrameError::FieldTooLong(key.to_string()))?;
+
+        payload.push(key_len);
+        payload.extend_from_slice(key.as_bytes());
+        payload.extend_from_slice(&value_len.to_be_bytes());
+        payload.extend_from_slice(value);
+    }
+
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(FrameError::TooLong(payload.len()));
+    }
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len() + CHECKSUM_LEN);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    frame.extend_from_slice(&crc32(&payload).to_be_bytes());
+
+    Ok(frame)
+}
+
+/// Читает один кадр из начала `bytes`.
+/// Возвращает поля и количество прочитанных байт
+pub fn decode(bytes: &[u8]) -> Result<(Fields, usize), FrameError> {
+    let Some(header) = bytes.get(..HEADER_LEN) else {
+        return Err(FrameError::Incomplete { needed: HEADER_LEN });
+    };
+
+    let payload_len = u32::from_be_bytes(header.try_into().expect("header is 4 bytes")) as usize;
+    if payload_len > MAX_PAYLOAD_LEN {
+        return Err(FrameError::TooLong(payload_len));
+    }
+
+    let frame_len = HEADER_LEN + payload_len + CHECKSUM_LEN;
+    if bytes.len() < frame_len {
+        return Err(FrameError::Incomplete { needed: frame_len });
+    }
+
+    let payload = &bytes[HEADER_LEN..HEADER_LEN + payload_len];
+    let expected = u32::from_be_bytes(
+        bytes[HEADER_LEN + payload_len..frame_len].try_into().expect("checksum is 4 bytes")
+    );
+    let actual = crc32(payload);
+    if expected != actual {
+        return Err(FrameError::ChecksumMismatch { expected, actual });
+    }
+
+    Ok((fields(payload)?, frame_len))
+}
+
+fn fields(payload: &[u8]) -> Result<Fields, FrameError> {
+    let mut fields = HashMap::new();
+    let mut offset = 0;
+
+    while offset < payload.len() {
+        let truncated = FrameError::TruncatedField { offset: HEADER_LEN + offset };
+
+        let key_len = payload[offset] as usize;
+        let key = payload
+            .get(offset + 1..offset + 1 + key_len)
+            .ok_or(truncated.clone())?;
+        let key = std::str::from_utf8(key)
+            .map_err(|_| FrameError::InvalidKey { offset: HEADER_LEN + offset })?;
+
+        let value_at = offset + 1 + key_len;
+        let value_len = payload
+            .get(value_at..value_at + 2)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+            .ok_or(truncated.clone())?;
+        let value = payload
+            .get(value_at + 2..value_at + 2 + value_len)
+            .ok_or(truncated)?;
+
+        if fields.insert(key.to_string(), value.to_vec()).is_some() {
+            return Err(FrameError::DuplicateField(key.to_string()));
+        }
+
+        offset = value_at + 2 + value_len;
+    }
+
+    Ok(fields)
+}
+
+/// Собирает кадры из потока байт, который приходит кусками произвольной длины:
+/// в одном куске может быть несколько кадров, а кадр может быть разрезан
+/// между кусками.
+///
+/// Разделителей между кадрами нет, поэтому после поврежденного кадра
+/// начало следующего не найти. Декодер сбрасывает буфер и возвращает
+/// ошибку, соединение после этого нужно закрыть
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавляет прочитанные байты в конец буфера
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Следующий целый кадр, `None`, если кадр еще не пришел целиком
+    pub fn next_frame(&mut self) -> Result<Option<SerializedGauge>, FrameError> {
+        match decode(&self.buffer) {
+            Ok((_, len)) => {
+                let frame: Vec<u8> = self.buffer.drain(..len).collect();
+                Ok(Some(frame.into()))
+            },
+            Err(FrameError::Incomplete { .. }) => Ok(None),
+            Err(e) => {
+                self.buffer.clear();
+                Err(e)
+            },
+        }
+    }
+
+    /// Читает из `reader`, пока не соберется кадр.
+    /// `None`, если соединение закрылось раньше
+    pub fn read_frame(&mut self, reader: &mut impl Read) -> crate::Result<Option<SerializedGauge>> {
+        let mut buffer = [0; 1024];
+
+        loop {
+            if let Some(frame) = self.next_frame()? {
+                return Ok(Some(frame));
+            }
+
+            match reader.read(&mut buffer)? {
+                0 => return Ok(None),
+                size => self.extend(&buffer[..size]),
+            }
+        }
+    }
+
+    /// Сколько байт ждут окончания кадра
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// CRC-32 (IEEE 802.3), как в zip и png
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::DeserializedGauge;
+
+    use super::{crc32, decode, encode, FrameDecoder, FrameError, HEADER_LEN};
+
+    #[test]
+    fn round_trip_with_delimiter_bytes() {
+        let state = [2, b';', b':', 0x04, 0x00];
+        let frame = encode(&[("id", b"temperature_gauge"), ("state", &state)]).unwrap();
+
+        let (fields, len) = decode(&frame).unwrap();
+        assert_eq!(len, frame.len());
+        assert_eq!(fields["state"], state);
+        assert_eq!(fields["id"], b"temperature_gauge");
+
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn hostile_input() {
+        let frame = encode(&[("name", b"Hall")]).unwrap();
+
+        for len in 0..frame.len() {
+            assert!(matches!(decode(&frame[..len]), Err(FrameError::Incomplete { .. })));
+        }
+
+        let mut corrupted = frame.clone();
+        corrupted[HEADER_LEN + 1] ^= 0xFF;
+        assert!(matches!(decode(&corrupted), Err(FrameError::ChecksumMismatch { .. })));
+
+        assert_eq!(decode(&[0xFF, 0xFF, 0xFF, 0xFF]), Err(FrameError::TooLong(u32::MAX as usize)));
+
+        // Длина ключа больше, чем осталось в кадре, контрольная сумма верна
+        let payload = [200, b'a'];
+        let mut truncated = (payload.len() as u32).to_be_bytes().to_vec();
+        truncated.extend_from_slice(&payload);
+        truncated.extend_from_slice(&crc32(&payload).to_be_bytes());
+        assert_eq!(decode(&truncated), Err(FrameError::TruncatedField { offset: HEADER_LEN }));
+
+        let twice = encode(&[("id", b"a"), ("id", b"b")]).unwrap();
+        assert_eq!(decode(&twice), Err(FrameError::DuplicateField("id".to_string())));
+    }
+
+    #[test]
+    fn decoder_reassembles_stream() {
+        let first = encode(&[("name", b"Hall")]).unwrap();
+        let second = encode(&[("name", b"Kitchen")]).unwrap();
+        let stream = [first.clone(), second.clone(), first[..3].to_vec()].concat();
+
+        let names = |decoder: &mut FrameDecoder| {
+            let mut names = vec![];
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                names.push(DeserializedGauge::parse(frame).unwrap().try_name().unwrap());
+            }
+            names
+        };
+
+        // Два кадра в одном куске и начало третьего
+        let mut decoder = FrameDecoder::new();
+        decoder.extend(&stream);
+        assert_eq!(names(&mut decoder), ["Hall", "Kitchen"]);
+        assert_eq!(decoder.buffered(), 3);
+
+        // Тот же поток по одному байту
+        let mut decoder = FrameDecoder::new();
+        let mut received = vec![];
+        for byte in &stream {
+            decoder.extend(&[*byte]);
+            received.extend(names(&mut decoder));
+        }
+        assert_eq!(received, ["Hall", "Kitchen"]);
+
+        let mut corrupted = second;
+        corrupted[HEADER_LEN + 2] ^= 0xFF;
+        decoder.extend(&corrupted);
+        assert!(matches!(decoder.next_frame(), Err(FrameError::ChecksumMismatch { .. })));
+        assert_eq!(decoder.buffered(), 0);
+    }
+}
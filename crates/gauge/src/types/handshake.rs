@@ -0,0 +1,335 @@
+//! Рукопожатие при подключении датчика к серверу
+//!
+//! Сразу после подключения датчик отправляет кадр [`Hello`] с версией
+//! протокола, типом датчика, идентификатором устройства и списком возможностей.
+//! Сервер отвечает кадром [`Reply`]: выбранная версия и общие возможности
+//! или причина отказа. После отказа сервер закрывает соединение.
+//!
+//! Версия в [`Hello`] - самая новая, которую знает датчик. Сервер выбирает
+//! меньшую из своей и версии датчика, поэтому новый сервер продолжает
+//! работать со старыми датчиками, пока их версия не ниже [`MIN_PROTOCOL_VERSION`].
+use std::{fmt::Display, io::{Read, Write}};
+
+use super::{fire_alarm::FIRE_ALARM_ID, device::DeviceId, frame::{self, FrameDecoder, Fields}, temperature_gauge::TEMPERATURE_GAUGE_ID, SerializedGauge};
+
+/// Текущая версия протокола
+pub const PROTOCOL_VERSION: u16 = 1;
+/// Самая старая версия, которую принимает сервер
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// Типы датчиков, которые знает сервер
+pub const GAUGE_TYPES: &[&[u8]] = &[FIRE_ALARM_ID, TEMPERATURE_GAUGE_ID];
+
+/// Датчик присылает свое состояние кадрами [`Gauge::serialize`](super::Gauge::serialize)
+pub const FEATURE_STATE: &str = "state";
+/// Датчик держит соединение открытым и выполняет команды [`command`](super::command)
+pub const FEATURE_COMMANDS: &str = "commands";
+/// Возможности, которые поддерживает сервер
+pub const FEATURES: &[&str] = &[FEATURE_STATE, FEATURE_COMMANDS];
+
+const ACCEPTED: &[u8] = b"accepted";
+const REJECTED: &[u8] = b"rejected";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeError {
+    /// Первый кадр соединения не [`Hello`]
+    NotHello,
+    MissingField(&'static str),
+    MalformedField(&'static str),
+    /// Сервер отказал датчику, причина для журнала датчика
+    Rejected(String),
+    /// Соединение закрылось до конца рукопожатия
+    Closed,
+}
+
+impl Display for HandshakeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandshakeError::NotHello =>
+                write!(f, "expected hello frame"),
+            HandshakeError::MissingField(field) =>
+                write!(f, "handshake field '{field}' missing"),
+            HandshakeError::MalformedField(field) =>
+                write!(f, "handshake field '{field}' is malformed"),
+            HandshakeError::Rejected(reason) =>
+                write!(f, "handshake rejected: {reason}"),
+            HandshakeError::Closed =>
+                write!(f, "connection closed during handshake"),
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+/// Приветствие датчика
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hello {
+    pub version: u16,
+    /// Идентификатор типа, например [`FIRE_ALARM_ID`]
+    pub gauge_type: Vec<u8>,
+    /// Серийный номер - [`DeviceId`] устройства. Кадры состояния
+    /// в этом соединении должны нести тот же идентификатор
+    pub serial: String,
+    pub features: Vec<String>,
+}
+
+impl Hello {
+    /// Приветствие с текущей версией протокола
+    pub fn new(gauge_type: &[u8], serial: &str, features: &[&str]) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            gauge_type: gauge_type.to_vec(),
+            serial: serial.to_string(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        Ok(frame::encode(&[
+            ("protocol", &self.version.to_be_bytes()),
+            ("type", &self.gauge_type),
+            ("serial", self.serial.as_bytes()),
+            ("features", self.features.join(",").as_bytes()),
+        ])?)
+    }
+
+    pub fn parse(gauge: SerializedGauge) -> crate::Result<Self> {
+        let (fields, _) = frame::decode(&gauge.0)?;
+
+        let Some(version) = fields.get("protocol") else {
+            return Err(HandshakeError::NotHello.into());
+        };
+        let version = version
+            .as_slice()
+            .try_into()
+            .map(u16::from_be_bytes)
+            .map_err(|_| HandshakeError::MalformedField("protocol"))?;
+
+        Ok(Self {
+            version,
+            gauge_type: required(&fields, "type")?.to_vec(),
+            serial: utf8(&fields, "serial")?,
+            features: features(&fields)?,
+        })
+    }
+}
+
+/// Ответ сервера на [`Hello`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Accepted { version: u16, features: Vec<String> },
+    Rejected { reason: String },
+}
+
+impl Reply {
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        let frame = match self {
+            Reply::Accepted { version, features } => frame::encode(&[
+                ("status", ACCEPTED),
+                ("protocol", &version.to_be_bytes()),
+                ("features", features.join(",").as_bytes()),
+            ]),
+            Reply::Rejected { reason } => frame::encode(&[
+                ("status", REJECTED),
+                ("reason", reason.as_bytes()),
+            ]),
+        };
+
+        Ok(frame?)
+    }
+
+    pub fn parse(gauge: SerializedGauge) -> crate::Result<Self> {
+        let (fields, _) = frame::decode(&gauge.0)?;
+
+        let reply = match required(&fields, "status")? {
+            ACCEPTED => Reply::Accepted {
+                version: required(&fields, "protocol")?
+                    .try_into()
+                    .map(u16::from_be_bytes)
+                    .map_err(|_| HandshakeError::MalformedField("protocol"))?,
+                features: features(&fields)?,
+            },
+            REJECTED => Reply::Rejected { reason: utf8(&fields, "reason")? },
+            _ => Err(HandshakeError::MalformedField("status"))?,
+        };
+
+        Ok(reply)
+    }
+}
+
+/// Решение сервера по приветствию датчика
+pub fn negotiate(hello: &Hello) -> Reply {
+    let reject = |reason: String| Reply::Rejected { reason };
+
+    if hello.version < MIN_PROTOCOL_VERSION {
+        return reject(format!(
+            "protocol version {} is not supported, minimum is {MIN_PROTOCOL_VERSION}",
+            hello.version
+        ));
+    }
+
+    if !GAUGE_TYPES.contains(&hello.gauge_type.as_slice()) {
+        return reject(format!("unknown gauge type '{}'", String::from_utf8_lossy(&hello.gauge_type)));
+    }
+
+    if let Err(e) = hello.serial.parse::<DeviceId>() {
+        return reject(e.to_string());
+    }
+
+    Reply::Accepted {
+        version: hello.version.min(PROTOCOL_VERSION),
+        features: hello.features
+            .iter()
+            .filter(|f| FEATURES.contains(&f.as_str()))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Рукопожатие со стороны датчика: отправляет `hello` и ждет ответ.
+/// Отказ сервера возвращается как [`HandshakeError::Rejected`]
+pub fn greet<S: Read + Write>(stream: &mut S, hello: &Hello) -> crate::Result<(u16, Vec<String>)> {
+    stream.write_all(&hello.encode()?)?;
+
+    let reply = FrameDecoder::new()
+        .read_frame(stream)?
+        .ok_or(HandshakeError::Closed)?;
+
+    match Reply::parse(reply)? {
+        Reply::Accepted { version, features } => Ok((version, features)),
+        Reply::Rejected { reason } => Err(HandshakeError::Rejected(reason))?,
+    }
+}
+
+/// Рукопожатие со стороны сервера: читает [`Hello`] и отвечает.
+///
+/// `decoder` остается с байтами, пришедшими после приветствия.
+/// При отказе возвращает [`HandshakeError::Rejected`], соединение нужно закрыть
+pub fn accept<S: Read + Write>(stream: &mut S, decoder: &mut FrameDecoder) -> crate::Result<(Hello, u16)> {
+    let hello = decoder
+        .read_frame(stream)
+        .and_then(|frame| Hello::parse(frame.ok_or(HandshakeError::Closed)?));
+
+    let hello = match hello {
+        Ok(hello) => hello,
+        Err(e) => {
+            // Ответ без гарантии доставки, датчик мог прислать что угодно
+            let _ = stream.write_all(&Reply::Rejected { reason: e.to_string() }.encode()?);
+            return Err(e);
+        },
+    };
+
+    let reply = negotiate(&hello);
+    stream.write_all(&reply.encode()?)?;
+
+    match reply {
+        Reply::Accepted { version, .. } => Ok((hello, version)),
+        Reply::Rejected { reason } => Err(HandshakeError::Rejected(reason))?,
+    }
+}
+
+fn required<'a>(fields: &'a Fields, field: &'static str) -> Result<&'a [u8], HandshakeError> {
+    fields
+        .get(field)
+        .map(|v| v.as_slice())
+        .ok_or(HandshakeError::MissingField(field))
+}
+
+fn utf8(fields: &Fields, field: &'static str) -> Result<String, HandshakeError> {
+    String::from_utf8(required(fields, field)?.to_vec())
+        .map_err(|_| HandshakeError::MalformedField(field))
+}
+
+fn features(fields: &Fields) -> Result<Vec<String>, HandshakeError> {
+    Ok(
+        utf8(fields, "features")?
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(|f| f.to_string())
+            .collect()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read, Write};
+
+    use crate::types::{fire_alarm::FIRE_ALARM_ID, frame::{self, FrameDecoder}};
+
+    use super::{accept, negotiate, HandshakeError, Hello, Reply, FEATURE_STATE, PROTOCOL_VERSION};
+
+    #[test]
+    fn negotiation() {
+        let hello = Hello::new(FIRE_ALARM_ID, "FA-0001", &[FEATURE_STATE, "firmware_update"]);
+        let parsed = Hello::parse(hello.encode().unwrap().into()).unwrap();
+        assert_eq!(parsed, hello);
+
+        assert_eq!(
+            negotiate(&Hello { version: PROTOCOL_VERSION + 1, ..hello.clone() }),
+            Reply::Accepted { version: PROTOCOL_VERSION, features: vec![FEATURE_STATE.to_string()] }
+        );
+
+        let rejected = |hello: Hello| match negotiate(&hello) {
+            Reply::Rejected { reason } => reason,
+            reply => panic!("{reply:?} accepted"),
+        };
+        assert_eq!(rejected(Hello { version: 0, ..hello.clone() }), "protocol version 0 is not supported, minimum is 1");
+        assert_eq!(rejected(Hello::new(b"toaster", "T-1", &[])), "unknown gauge type 'toaster'");
+        assert_eq!(rejected(Hello::new(FIRE_ALARM_ID, " ", &[])), "device id must be 1 to 64 characters long");
+        assert!(rejected(Hello::new(FIRE_ALARM_ID, "Living room alarm", &[])).starts_with("device id may contain"));
+
+        let reply = Reply::Rejected { reason: "device id must be 1 to 64 characters long".to_string() };
+        assert_eq!(Reply::parse(reply.encode().unwrap().into()).unwrap(), reply);
+    }
+
+    /// Входящие байты из `input`, исходящие копятся в `output`
+    struct Connection {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Connection {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Connection {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn server_side() {
+        let hello = Hello::new(FIRE_ALARM_ID, "FA-0001", &[FEATURE_STATE]).encode().unwrap();
+        let state = frame::encode(&[("name", b"Hall")]).unwrap();
+
+        let mut connection = Connection { input: Cursor::new([hello, state.clone()].concat()), output: vec![] };
+        let mut decoder = FrameDecoder::new();
+
+        let (hello, version) = accept(&mut connection, &mut decoder).unwrap();
+        assert_eq!((hello.serial.as_str(), version), ("FA-0001", PROTOCOL_VERSION));
+        assert!(matches!(Reply::parse(connection.output.into()).unwrap(), Reply::Accepted { .. }));
+        // Кадр после приветствия не потерялся
+        assert_eq!(decoder.read_frame(&mut connection.input).unwrap().map(|f| f.0), Some(state.clone()));
+
+        // Старый датчик сразу шлет состояние
+        let mut connection = Connection { input: Cursor::new(state), output: vec![] };
+        let error = accept(&mut connection, &mut FrameDecoder::new()).unwrap_err();
+        assert_eq!(error.downcast_ref::<HandshakeError>(), Some(&HandshakeError::NotHello));
+        assert_eq!(
+            Reply::parse(connection.output.into()).unwrap(),
+            Reply::Rejected { reason: "expected hello frame".to_string() }
+        );
+
+        // Датчик со старым текстовым протоколом
+        let mut connection = Connection { input: Cursor::new(b"id:fire_alarm;name:Hall\x04".to_vec()), output: vec![] };
+        assert!(accept(&mut connection, &mut FrameDecoder::new()).is_err());
+        assert!(matches!(Reply::parse(connection.output.into()).unwrap(), Reply::Rejected { .. }));
+    }
+}
@@ -18,6 +18,8 @@
 //! ## Имя (Name)
 //!
 //! Как датчик представляет себя пользователю.
+//! Имя может повторяться и меняться, поэтому конкретное устройство
+//! определяется по [`device::DeviceId`].
 //!
 //! ## Состояние (State)
 //!
@@ -27,6 +29,17 @@
 //! для этого необходимо определить логику для сериализиции [`Gauge::serialize_state`] и десериализации
 //! состояния [`GaugeState::parse_state`]
 //!
+//! ## Команды (Command)
+//!
+//! Сервер может управлять датчиком командами - `enum`, который реализует [`GaugeCommand`].
+//! Команды и ответы на них передаются кадрами [`command`], если при рукопожатии
+//! обе стороны заявили возможность [`handshake::FEATURE_COMMANDS`]
+//!
+//! ## Передача
+//!
+//! Датчик передается одним кадром [`frame`] с полями `id`, `name`, `state`
+//! и `device` после рукопожатия [`handshake`].
+//!
 //! ## Примеры 
 //! 
 //! [`fire_alarm`]
@@ -34,14 +47,18 @@
 //! 
 
 
+pub mod client;
+pub mod command;
+pub mod device;
 pub mod fire_alarm;
+pub mod frame;
+pub mod handshake;
 pub mod temperature_gauge;
 
-const END_OF_TRANSMISSION: char = '\x04';
-
-use std::{borrow::Borrow, collections::HashMap, fmt::{Debug, Display}, ops::Deref};
+use std::{collections::HashMap, fmt::{Debug, Display}, str::FromStr};
 use json_minimal::Json;
-use crate::Result;
+use device::DeviceId;
+use crate::{helpers::{JsonError, json_check_field, json_check_string, json_required}, house_layout::dimensions::Position, Result};
 
 type GaugeIdentifier = Vec<u8>;
 type GaugeName = String;
@@ -55,21 +72,26 @@ pub trait GaugeState: Debug + Display
     }
 }
 
+/// Первый байт - код команды, остальные - аргументы
+pub type SerializedCommand = Vec<u8>;
+
+/// Команда датчику. Текстовый вид ([`Display`], [`FromStr`]) - для людей,
+/// например `enable` или `interval 10`
+pub trait GaugeCommand: Debug + Display + FromStr<Err = crate::Error>
+{
+    fn parse_command(command: SerializedCommand) -> Result<Self> where Self: Sized;
+    fn serialize_command(&self) -> SerializedCommand;
+}
+
 type SerializedGaugeBytes = Vec<u8>;
 
-/// Сериализованный счетчик
+/// Сериализованный счетчик, байты кадра [`frame`]
 pub struct SerializedGauge(SerializedGaugeBytes);
 
-/// Читает сериализованный счетчик в виде TCP сообщения,
-/// которое оканчивается символом [`END_OF_TRANSMISSION`]
+/// Байты, начинающиеся с кадра. Все, что идет после кадра, игнорируется
 impl From<SerializedGaugeBytes> for SerializedGauge {
     fn from(value: SerializedGaugeBytes) -> Self {
-        let bytes = value.split(|byte| 
-            { *byte as char == END_OF_TRANSMISSION }
-        )
-            .collect::<Vec<&[u8]>>()[0];
-
-        Self(bytes.to_vec())
+        Self(value)
     }
 }
 
@@ -86,6 +108,7 @@ where
 {
     // Deserialisation
     type GaugeState: GaugeState;
+    type GaugeCommand: GaugeCommand;
 
     fn new(name: GaugeName, state: Self::GaugeState) -> Self where Self: Sized;
 
@@ -97,8 +120,12 @@ where
 
     fn id(&self) -> GaugeIdentifier;
 
+    /// Устройство, `None`, если датчик создан не устройством
+    fn device(&self) -> Option<&DeviceId>;
+    fn set_device(&mut self, device: DeviceId);
+
     fn deserialize(gauge: SerializedGauge) -> crate::Result<Self> where Self: Sized {
-        let deserialized_gauge = DeserializedGauge::parse(gauge);
+        let deserialized_gauge = DeserializedGauge::parse(gauge)?;
 
         Ok(Self::parse(deserialized_gauge)?)
     }
@@ -107,7 +134,12 @@ where
         let name = deserialized_gauge.try_name()?;
         let state = Self::GaugeState::parse_state(deserialized_gauge.try_state()?)?;
 
-        Ok(Self::new( name, state))
+        let mut gauge = Self::new( name, state);
+        if let Some(device) = deserialized_gauge.try_device()? {
+            gauge.set_device(device);
+        }
+
+        Ok(gauge)
     }
 
     // Serialization
@@ -116,9 +148,11 @@ where
     fn serialize_name(&self) -> SerializedGaugeName;
     fn serialize_state(&self) -> SerializedGaugeState;
 
-    /// Сериализует счетчик в вид `Ключ:Значение;Ключ:Значение`,
-    /// Для дальнейщей десериализации в Хэш Таблиц (см. [`DeserializedGauge`]).
-    fn serialize(&self) -> SerializedGaugeBytes where Self: Sized {
+    /// Сериализует счетчик в кадр [`frame`] с полями `id`, `name`, `state`
+    /// и `device`, если устройство известно, для дальнейщей десериализации в Хэш Таблицу (см. [`DeserializedGauge`]).
+    ///
+    /// Ошибка, если имя не помещается в кадр
+    fn serialize(&self) -> crate::Result<SerializedGaugeBytes> where Self: Sized {
         let id = self.id();
         let name = self.name().as_bytes().to_vec();
 
@@ -132,28 +166,16 @@ where
         ]
             .concat();
 
-        let mut map: HashMap<&str, Vec<u8>> = HashMap::new();
-        let mut serialized_slice = Vec::new();
-
-        map.insert("id", id);
-        map.insert("name", name);
-        map.insert("state", state);
-
-        println!("{:?}",&map);
-
-        for (key, value) in map {
-            serialized_slice.push([
-                key, 
-                &unsafe { String::from_utf8_unchecked(value) }
-            ].join(":"))
+        let mut fields: Vec<(&str, &[u8])> = vec![
+            ("id", &id),
+            ("name", &name),
+            ("state", &state),
+        ];
+        if let Some(device) = self.device() {
+            fields.push(("device", device.as_str().as_bytes()));
         }
 
-        let mut serialized_string = serialized_slice.join(";");
-        serialized_string.push(END_OF_TRANSMISSION);
-       
-        println!("{:?}",serialized_string);
-
-        serialized_string.into_bytes()
+        Ok(frame::encode(&fields)?)
     }
 }
 
@@ -189,23 +211,20 @@ impl DeserializedGauge {
         }
     }
 
-    pub fn parse(gauge: SerializedGauge) -> Self {
-        let bytes_as_string = unsafe { String::from_utf8_unchecked(gauge.0) };
-
-        println!("{:?}",bytes_as_string);
-        
-        let map: HashMap<String, Vec<u8>> = HashMap::from(
-            bytes_as_string.split(';')
-                .map(|val| {
-                    let key_value: Vec<&str> = val.split(':').collect();
-                    (key_value[0].to_string(),key_value[1].as_bytes().to_vec())
-                })
-                .collect::<HashMap<String,Vec<u8>>>()
-        );
+    /// Читает идентификатор устройства, `None`, если его нет в кадре
+    pub fn try_device(&self) -> Result<Option<DeviceId>> {
+        match self.0.get("device") {
+            Some(val) => Ok(Some(std::str::from_utf8(val)?.parse()?)),
+            None => Ok(None),
+        }
+    }
 
-        println!("{:?}",map);
+    /// Читает кадр из начала сериализованного счетчика.
+    /// Неполный или поврежденный кадр - ошибка [`frame::FrameError`]
+    pub fn parse(gauge: SerializedGauge) -> Result<Self> {
+        let (fields, _) = frame::decode(&gauge.0)?;
 
-        Self(map)
+        Ok(Self(fields))
     }
 
 }
@@ -213,7 +232,7 @@ impl DeserializedGauge {
 impl Display for DeserializedGauge {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let (state,state_message): (String,String) = match self.try_state() {
-            Ok(state) => {
+            Ok(state) if !state.is_empty() => {
                 let state_id = format!("{}",state[0]);
                 let state_message: String;
 
@@ -228,15 +247,22 @@ impl Display for DeserializedGauge {
 
                 (state_id,state_message)
             },
-            Err(_) => {
+            _ => {
                 ("<Malformed state>".into(),"<Malformed state message>".into())
             },
         };
         let id_bytes = self.try_id().unwrap_or("<Malformed id>".into());
 
-        write!(f,"Name: {},\nId: {},\nState: {},\nState message: {}",
+        let device = match self.try_device() {
+            Ok(Some(device)) => device.to_string(),
+            Ok(None) => "None".to_string(),
+            Err(_) => "<Malformed device>".to_string(),
+        };
+
+        write!(f,"Name: {},\nId: {},\nDevice: {},\nState: {},\nState message: {}",
             self.try_name().unwrap_or("<Malformed name>".into()),
             String::from_utf8(id_bytes).unwrap_or("<Malformed id>".to_string()),
+            device,
             state,
             state_message
         )
@@ -264,28 +290,96 @@ impl GaugeJson {
         name: &str,
         state: &dyn GaugeState
     ) -> Self {
-        Self(
-            Json::JSON(vec![
-                Json::OBJECT {
-                    name: "id".to_string(),
-                    value: Box::new(
-                        Json::STRING(String::from_utf8_lossy(&id).to_string())
-                    )
-                },
-                Json::OBJECT {
-                    name: "name".to_string(),
-                    value: Box::new(
-                        Json::STRING(name.to_string())
-                    )
-                },
-                Json::OBJECT {
-                    name: "state".to_string(),
-                    value: Box::new(
-                        state.json()
-                    )
-                },
-            ])
-        )
+        Self::from_fields(&String::from_utf8_lossy(id), name, state.json(), None, None)
+    }
+
+    fn from_fields(id: &str, name: &str, state: Json, position: Option<Position>, device: Option<DeviceId>) -> Self {
+        let mut fields = vec![
+            Json::OBJECT {
+                name: "id".to_string(),
+                value: Box::new(
+                    Json::STRING(id.to_string())
+                )
+            },
+            Json::OBJECT {
+                name: "name".to_string(),
+                value: Box::new(
+                    Json::STRING(name.to_string())
+                )
+            },
+            Json::OBJECT {
+                name: "state".to_string(),
+                value: Box::new(
+                    state
+                )
+            },
+        ];
+
+        if let Some(position) = position {
+            fields.push(Json::OBJECT {
+                name: "position".to_string(),
+                value: Box::new(position.into())
+            });
+        }
+
+        if let Some(device) = device {
+            fields.push(Json::OBJECT {
+                name: "device".to_string(),
+                value: Box::new(Json::STRING(device.to_string()))
+            });
+        }
+
+        Self(Json::JSON(fields))
+    }
+
+    fn field(&self, field: &str) -> &str {
+        match self.0.get(field).map(|v| v.unbox()) {
+            Some(Json::STRING(value)) => value,
+            _ => "",
+        }
+    }
+
+    /// Идентификатор типа датчика
+    pub fn id(&self) -> &str {
+        self.field("id")
+    }
+
+    pub fn name(&self) -> &str {
+        self.field("name")
+    }
+
+    /// Состояние в виде [`GaugeState::json`]
+    pub fn state(&self) -> &str {
+        self.field("state")
+    }
+
+    /// Где датчик установлен на этаже, `None`, если он еще не размещен
+    pub fn position(&self) -> Option<Position> {
+        json_position(&self.0).ok().flatten()
+    }
+
+    /// Устройство, которое установлено на место датчика, `None`, если неизвестно
+    pub fn device(&self) -> Option<DeviceId> {
+        json_device(&self.0).ok().flatten()
+    }
+
+    /// Обновляет поля `name`, `state`, `position` и `device`, если они указаны.
+    /// `position: null` снимает датчик с плана, `device: null` отвязывает устройство
+    pub fn patch(&mut self, value: &Json) -> Result<()> {
+        let name = json_check_string(value, "name", Ok(self.name().to_string()))?;
+        let state = json_check_string(value, "state", Ok(self.state().to_string()))?;
+        let position = match value.get("position") {
+            Some(_) => json_position(value)?,
+            None => self.position(),
+        };
+        let device = match value.get("device") {
+            Some(_) => json_device(value)?,
+            None => self.device(),
+        };
+
+        *self = Self::from_fields(self.id(), &name, Json::STRING(state), position, device);
+
+        Ok(())
     }
 
     pub fn json(self) -> Json {
@@ -296,30 +390,45 @@ impl GaugeJson {
 impl TryFrom<&Json> for GaugeJson {
     type Error = crate::Error;
 
+    /// Requered fields:
+    /// `id`
+    /// `name`
+    /// `state`
+    /// Additional fields:
+    /// `position`
+    /// `device`
     fn try_from(value: &Json) -> std::result::Result<Self, Self::Error> {
-        const MALFORMED_JSON: &str = "Malformed json";
-
-        let check_field =
-        |val: &str| -> crate::Result<()> {
-            match
-                value.get(val)
-                    .ok_or([val, "field required"].join(" "))?
-                {
-                    Json::OBJECT { value, .. } => {
-                        match value.unbox() {
-                            Json::STRING(_) => Ok(()),
-                            _ => Err([val, "expected to be a string"].join(" "))?
-                        }
-                    },
-                     _ => Err(MALFORMED_JSON)?
-                }
+        let required = |field: &str| -> crate::Result<String> {
+            json_check_string(value, field, json_required(field))
         };
 
-        for val in ["id", "name", "gauge"] {
-            check_field(val)?
-        }
+        let id = required("id")?;
+        let name = required("name")?;
+        let state = required("state")?;
+
+        let position = json_position(value)?;
+        let device = json_device(value)?;
+
+        Ok(Self::from_fields(&id, &name, Json::STRING(state), position, device))
+    }
+}
+
+/// Поле `device` с [`DeviceId`], `null` и отсутствие поля означают, что устройство не привязано
+fn json_device(value: &Json) -> Result<Option<DeviceId>> {
+    match value.get("device").map(|v| v.unbox()) {
+        None | Some(Json::NULL) => Ok(None),
+        Some(_) => json_check_string(value, "device", json_required("device"))?
+            .parse()
+            .map(Some)
+            .map_err(|e: crate::Error| JsonError::field("device", e.to_string()).into()),
+    }
+}
 
-        Ok(Self(*value))
+/// Поле `position`, `null` и отсутствие поля означают, что датчик не размещен
+fn json_position(value: &Json) -> Result<Option<Position>> {
+    match value.get("position").map(|v| v.unbox()) {
+        None | Some(Json::NULL) => Ok(None),
+        Some(_) => Ok(Some(json_check_field(value, "position", json_required("position"))?)),
     }
 }
 
@@ -333,7 +442,7 @@ enum DeserializedGaugeError {
 /// Выводит Debug
 impl Display for DeserializedGaugeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f,"{}",self)
+        write!(f,"{:?}",self)
     }
 }
 
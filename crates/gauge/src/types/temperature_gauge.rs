@@ -1,10 +1,12 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr, time::Duration};
 
-use super::{GaugeIdentifier, GaugeState, Gauge};
+use super::{device::DeviceId, GaugeCommand, GaugeIdentifier, GaugeState, Gauge};
 
 pub const TEMPERATURE_GAUGE_ID: &[u8] = "temperature_gauge".as_bytes();
 
-type Temperature = f32;
+pub type Temperature = f32;
+
+const READED_TEMPERATURE: &str = "Readed temperature: ";
 
 #[derive(Debug, PartialEq)]
 pub enum TemperatureGaugeState {
@@ -18,19 +20,40 @@ impl Display for TemperatureGaugeState {
         let message = match self {
             TemperatureGaugeState::Disabled => "Disabled".to_string(),
             TemperatureGaugeState::Enabled => "Enabled".to_string(),
-            TemperatureGaugeState::ReadedTemperarure(t) => format!("Readed temperature: {}",t),
+            TemperatureGaugeState::ReadedTemperarure(t) => format!("{READED_TEMPERATURE}{}",t),
         };
 
         write!(f,"{}",message)
     }
 }
 
+/// Разбирает строку в формате [`Display`], например состояние из [`GaugeJson`](super::GaugeJson)
+impl FromStr for TemperatureGaugeState {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let state = match s {
+            "Disabled" => TemperatureGaugeState::Disabled,
+            "Enabled" => TemperatureGaugeState::Enabled,
+            _ => {
+                let temp = s
+                    .strip_prefix(READED_TEMPERATURE)
+                    .ok_or("Unknown Temperature Gauge State")?
+                    .parse()?;
+                TemperatureGaugeState::ReadedTemperarure(temp)
+            },
+        };
+
+        Ok(state)
+    }
+}
+
 impl GaugeState for TemperatureGaugeState {
     fn parse_state(state: super::SerializedState) -> crate::Result<Self> {
-        let state = match state[0] {
-            0 => TemperatureGaugeState::Disabled,
-            1 => TemperatureGaugeState::Enabled,
-            2 => {
+        let state = match state.first() {
+            Some(0) => TemperatureGaugeState::Disabled,
+            Some(1) => TemperatureGaugeState::Enabled,
+            Some(2) => {
                 let temp_as_bytes: [u8;4]  = state[1..].try_into()?;
                 let temp = f32::from_be_bytes(temp_as_bytes);
                 TemperatureGaugeState::ReadedTemperarure(temp)
@@ -42,17 +65,91 @@ impl GaugeState for TemperatureGaugeState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureGaugeCommand {
+    Enable,
+    Disable,
+    /// Как часто отправлять температуру, в целых секундах, не меньше секунды
+    SetInterval(Duration),
+    SelfTest,
+}
+
+impl Display for TemperatureGaugeCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemperatureGaugeCommand::Enable => write!(f,"enable"),
+            TemperatureGaugeCommand::Disable => write!(f,"disable"),
+            TemperatureGaugeCommand::SetInterval(interval) => write!(f,"interval {}",interval.as_secs()),
+            TemperatureGaugeCommand::SelfTest => write!(f,"self-test"),
+        }
+    }
+}
+
+impl FromStr for TemperatureGaugeCommand {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let command = match s.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["enable"] => TemperatureGaugeCommand::Enable,
+            ["disable"] => TemperatureGaugeCommand::Disable,
+            ["interval", secs] => TemperatureGaugeCommand::SetInterval(interval(secs.parse()?)?),
+            ["self-test"] => TemperatureGaugeCommand::SelfTest,
+            _ => Err("Unknown Temperature Gauge command, expected enable, disable, interval <secs> or self-test")?
+        };
+
+        Ok(command)
+    }
+}
+
+impl GaugeCommand for TemperatureGaugeCommand {
+    fn parse_command(command: super::SerializedCommand) -> crate::Result<Self> {
+        let command = match command.as_slice() {
+            [0] => TemperatureGaugeCommand::Enable,
+            [1] => TemperatureGaugeCommand::Disable,
+            [2, secs @ ..] => {
+                let secs_as_bytes: [u8;4] = secs.try_into()?;
+                TemperatureGaugeCommand::SetInterval(interval(u32::from_be_bytes(secs_as_bytes))?)
+            },
+            [3] => TemperatureGaugeCommand::SelfTest,
+            _ => Err("Unknown Temperature Gauge command")?
+        };
+
+        Ok(command)
+    }
+
+    fn serialize_command(&self) -> super::SerializedCommand {
+        match self {
+            TemperatureGaugeCommand::Enable => vec![0],
+            TemperatureGaugeCommand::Disable => vec![1],
+            TemperatureGaugeCommand::SetInterval(interval) => {
+                let secs = u32::try_from(interval.as_secs()).unwrap_or(u32::MAX);
+                [vec![2], secs.to_be_bytes().to_vec()].concat()
+            },
+            TemperatureGaugeCommand::SelfTest => vec![3],
+        }
+    }
+}
+
+fn interval(secs: u32) -> crate::Result<Duration> {
+    match secs {
+        0 => Err("Interval must be at least 1 second")?,
+        secs => Ok(Duration::from_secs(secs as u64)),
+    }
+}
+
 #[derive(Debug,PartialEq)]
 pub struct TemperatureGauge {
     name: String,
-    state: TemperatureGaugeState
+    state: TemperatureGaugeState,
+    device: Option<DeviceId>,
 }
 
 impl Gauge for TemperatureGauge {
     type GaugeState = TemperatureGaugeState;
+    type GaugeCommand = TemperatureGaugeCommand;
 
     fn new(name: super::GaugeName, state: Self::GaugeState) -> Self {
-        Self { name, state }
+        Self { name, state, device: None }
     }
 
     fn state(&self) -> &Self::GaugeState {
@@ -71,6 +168,14 @@ impl Gauge for TemperatureGauge {
         self.name = name
     }
 
+    fn device(&self) -> Option<&DeviceId> {
+        self.device.as_ref()
+    }
+
+    fn set_device(&mut self, device: DeviceId) {
+        self.device = Some(device)
+    }
+
     fn id(&self) -> GaugeIdentifier {
         TEMPERATURE_GAUGE_ID.to_vec()
     }
@@ -113,19 +218,58 @@ impl Display for TemperatureGauge {
 
 #[cfg(test)]
 mod test {
-    use crate::types::Gauge;
+    use std::time::Duration;
 
-    use super::{TemperatureGauge, TemperatureGaugeState};
+    use crate::types::{Gauge, GaugeCommand};
+
+    use super::{TemperatureGauge, TemperatureGaugeCommand, TemperatureGaugeState};
 
     #[test]
     fn serialize_and_deserialize() {
         let temp = TemperatureGauge::new("Room".to_string(), TemperatureGaugeState::ReadedTemperarure(36.6));
 
-        let serialized = temp.serialize();
+        let serialized = temp.serialize().unwrap();
 
         let deserialized = TemperatureGauge::deserialize(serialized.into())
             .expect("Error deserializng");
 
         assert_eq!(temp,deserialized)
     }
+
+    #[test]
+    fn reading_with_delimiter_bytes() {
+        // Байты `;`, `0x04` и `:` ломали старый формат `ключ:значение;`
+        let reading = f32::from_be_bytes([0x41, b';', 0x04, b':']);
+        let temp = TemperatureGauge::new("Room".to_string(), TemperatureGaugeState::ReadedTemperarure(reading));
+
+        let deserialized = TemperatureGauge::deserialize(temp.serialize().unwrap().into())
+            .expect("Error deserializng");
+        assert_eq!(temp, deserialized);
+
+        assert!(TemperatureGauge::deserialize(b"id:temperature_gauge;state:\x02".to_vec().into()).is_err());
+
+        let empty_state = crate::types::frame::encode(&[("name", b"Room"), ("state", b"")]).unwrap();
+        assert!(TemperatureGauge::deserialize(empty_state.into()).is_err());
+    }
+
+    #[test]
+    fn state_from_display() {
+        let state = TemperatureGaugeState::ReadedTemperarure(-3.5);
+
+        assert_eq!(state.to_string().parse::<TemperatureGaugeState>().unwrap(), state);
+        assert!("Readed temperature: hot".parse::<TemperatureGaugeState>().is_err());
+    }
+
+    #[test]
+    fn commands() {
+        let interval = TemperatureGaugeCommand::SetInterval(Duration::from_secs(30));
+
+        assert_eq!(TemperatureGaugeCommand::parse_command(interval.serialize_command()).unwrap(), interval);
+        assert_eq!("interval 30".parse::<TemperatureGaugeCommand>().unwrap(), interval);
+        assert_eq!(interval.to_string(), "interval 30");
+
+        assert!("interval 0".parse::<TemperatureGaugeCommand>().is_err());
+        assert!("silence".parse::<TemperatureGaugeCommand>().is_err());
+        assert!(TemperatureGaugeCommand::parse_command(vec![2, 0, 0]).is_err());
+    }
 }
@@ -0,0 +1,290 @@
+//! Журнал изменений дома
+//!
+//! Журнал только дополняется: отмена и повтор тоже записываются
+//! как новые ревизии, которые возвращают дом к одной из прошлых.
+//! Каждая запись хранит дом целиком, поэтому любую ревизию
+//! можно посмотреть без повторного применения изменений.
+//! Хранятся только последние [`RETENTION`] ревизий, более старые
+//! нельзя ни посмотреть, ни вернуть отменой.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gauge::{helpers::{json_check_number, json_check_string}, house_layout::house::House};
+use json_minimal::Json;
+
+/// Сколько последних ревизий хранится в журнале
+pub(crate) const RETENTION: usize = 100;
+
+const UNDO: &str = "undo";
+const REDO: &str = "redo";
+
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub(crate) revision: u64,
+    /// Секунды с начала эпохи Unix
+    pub(crate) timestamp: u64,
+    pub(crate) action: String,
+    pub(crate) path: String,
+    pub(crate) house: House,
+}
+
+impl Entry {
+    /// Запись без дома, для `/house/history`
+    pub(crate) fn summary(&self) -> Json {
+        Json::JSON(vec![
+            Json::OBJECT {
+                name: "revision".to_string(),
+                value: Box::new(Json::NUMBER(self.revision as f64))
+            },
+            Json::OBJECT {
+                name: "timestamp".to_string(),
+                value: Box::new(Json::NUMBER(self.timestamp as f64))
+            },
+            Json::OBJECT {
+                name: "action".to_string(),
+                value: Box::new(Json::STRING(self.action.clone()))
+            },
+            Json::OBJECT {
+                name: "path".to_string(),
+                value: Box::new(Json::STRING(self.path.clone()))
+            },
+        ])
+    }
+}
+
+impl Into<Json> for Entry {
+    fn into(self) -> Json {
+        let mut json = self.summary();
+
+        if let Json::JSON(fields) = &mut json {
+            fields.push(Json::OBJECT {
+                name: "house".to_string(),
+                value: Box::new(Json::JSON(vec![self.house.into()]))
+            });
+        }
+
+        json
+    }
+}
+
+impl TryFrom<&Json> for Entry {
+    type Error = Box<dyn std::error::Error>;
+
+    /// Requered fields:
+    /// `revision`
+    /// `timestamp`
+    /// `action`
+    /// `path`
+    /// `house`
+    fn try_from(value: &Json) -> Result<Self, Self::Error> {
+        let revision: f64 = json_check_number(value, "revision", Err("revision field required".into()))?;
+        let timestamp: f64 = json_check_number(value, "timestamp", Err("timestamp field required".into()))?;
+        let action = json_check_string(value, "action", Err("action field required".into()))?;
+        let path = json_check_string(value, "path", Err("path field required".into()))?;
+
+        let house = match value.get("house") {
+            Some(house) => House::try_from(house.unbox())?,
+            None => Err("house field required")?,
+        };
+
+        Ok(Self {
+            revision: revision as u64,
+            timestamp: timestamp as u64,
+            action,
+            path,
+            house,
+        })
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Journal {
+    entries: Vec<Entry>,
+    /// Ревизии, к которым вернет отмена, последняя сверху
+    undo: Vec<u64>,
+    /// Ревизии, к которым вернет повтор, последняя сверху
+    redo: Vec<u64>,
+}
+
+impl Journal {
+    /// Восстанавливает журнал и стеки отмены из сохраненных записей.
+    /// Записи должны идти подряд, но не обязательно с нулевой ревизии
+    pub(crate) fn replay(entries: Vec<Entry>) -> Self {
+        let mut journal = Self::default();
+
+        for entry in entries {
+            journal.record(entry);
+        }
+
+        journal
+    }
+
+    /// Текущая ревизия, `None` для пустого журнала
+    pub(crate) fn revision(&self) -> Option<u64> {
+        self.entries.last().map(|e| e.revision)
+    }
+
+    /// Запись ревизии `revision`, `None`, если ее нет или она уже удалена
+    pub(crate) fn get(&self, revision: u64) -> Option<&Entry> {
+        let first = self.entries.first()?.revision;
+
+        self.entries.get(usize::try_from(revision.checked_sub(first)?).ok()?)
+    }
+
+    pub(crate) fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Запись изменения `house`, которая станет следующей ревизией.
+    /// Журнал не меняется, пока запись не передана в [`Journal::record`]
+    pub(crate) fn prepare(&self, action: &str, path: &str, house: House) -> Entry {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        Entry {
+            revision: self.revision().map_or(0, |r| r + 1),
+            timestamp,
+            action: action.to_string(),
+            path: path.to_string(),
+            house,
+        }
+    }
+
+    /// Запись, которая вернет дом к ревизии до последнего изменения
+    pub(crate) fn prepare_undo(&self, path: &str) -> Option<Entry> {
+        let target = self.get(*self.undo.last()?)?;
+
+        Some(self.prepare(UNDO, path, target.house.clone()))
+    }
+
+    /// Запись, которая вернет последнее отмененное изменение
+    pub(crate) fn prepare_redo(&self, path: &str) -> Option<Entry> {
+        let target = self.get(*self.redo.last()?)?;
+
+        Some(self.prepare(REDO, path, target.house.clone()))
+    }
+
+    /// Добавляет запись, подготовленную [`Journal::prepare`] и остальными.
+    /// Записи старше [`RETENTION`] ревизий удаляются
+    pub(crate) fn record(&mut self, entry: Entry) -> &Entry {
+        self.track(&entry.action);
+        self.entries.push(entry);
+
+        if self.entries.len() > RETENTION {
+            self.entries.drain(..self.entries.len() - RETENTION);
+
+            let first = self.entries[0].revision;
+            self.undo.retain(|r| *r >= first);
+            self.redo.retain(|r| *r >= first);
+        }
+
+        self.entries.last().expect("entry was just pushed")
+    }
+
+    /// Обновляет стеки отмены перед записью `action`.
+    /// Ревизия, к которой вернулась отмена, могла быть уже удалена,
+    /// но текущая все равно попадает в стек повтора
+    fn track(&mut self, action: &str) {
+        let current = self.revision();
+
+        match action {
+            UNDO => {
+                self.undo.pop();
+                self.redo.extend(current);
+            },
+            REDO => {
+                self.redo.pop();
+                self.undo.extend(current);
+            },
+            _ => {
+                self.undo.extend(current);
+                self.redo.clear();
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gauge::house_layout::house::House;
+
+    use super::{Journal, RETENTION};
+
+    fn floors(journal: &Journal) -> usize {
+        journal.entries().last().unwrap().house.floors().len()
+    }
+
+    #[test]
+    fn undo_and_redo_append_revisions() {
+        let mut journal = Journal::default();
+        let mut house = House::default();
+
+        let record = |journal: &mut Journal, action: &str, house: &House| {
+            let entry = journal.prepare(action, "/house/floor", house.clone());
+            journal.record(entry);
+        };
+        let undo = |journal: &mut Journal| journal
+            .prepare_undo("/house/undo")
+            .map(|entry| journal.record(entry).revision);
+
+        record(&mut journal, "load", &house);
+        house.push_floor();
+        record(&mut journal, "add floor", &house);
+        house.push_floor();
+        record(&mut journal, "add floor", &house);
+
+        // Подготовленная запись ничего не меняет
+        journal.prepare_undo("/house/undo");
+        assert_eq!(journal.revision(), Some(2));
+
+        assert_eq!(undo(&mut journal), Some(3));
+        assert_eq!(floors(&journal), 2);
+        undo(&mut journal);
+        assert_eq!(floors(&journal), 1);
+        assert!(undo(&mut journal).is_none());
+
+        let redo = journal.prepare_redo("/house/redo").unwrap();
+        journal.record(redo);
+        assert_eq!(floors(&journal), 2);
+
+        let replayed = Journal::replay(journal.entries().to_vec());
+        assert_eq!(replayed.undo, journal.undo);
+        assert_eq!(replayed.redo, journal.redo);
+
+        // Новое изменение сбрасывает повтор
+        record(&mut journal, "add room", &house);
+        assert!(journal.prepare_redo("/house/redo").is_none());
+        assert_eq!(journal.revision(), Some(6));
+    }
+
+    #[test]
+    fn old_revisions_are_dropped() {
+        let mut journal = Journal::default();
+        let mut house = House::default();
+
+        for _ in 0..RETENTION + 5 {
+            house.push_floor();
+            let entry = journal.prepare("add floor", "/house/floor", house.clone());
+            journal.record(entry);
+        }
+
+        assert_eq!(journal.entries().len(), RETENTION);
+        assert_eq!(journal.revision(), Some(RETENTION as u64 + 4));
+        assert!(journal.get(4).is_none());
+        assert_eq!(journal.get(5).unwrap().house.floors().len(), 7);
+
+        // Отмена тоже добавляет ревизии и доходит только до сохраненных
+        let mut undone = 0;
+        while let Some(entry) = journal.prepare_undo("/house/undo") {
+            journal.record(entry);
+            undone += 1;
+        }
+        assert_eq!(undone, RETENTION / 2);
+        assert!(journal.entries().first().unwrap().revision <= *journal.redo.first().unwrap());
+
+        let replayed = Journal::replay(journal.entries().to_vec());
+        assert_eq!(replayed.revision(), journal.revision());
+        assert_eq!(replayed.redo, journal.redo);
+    }
+}
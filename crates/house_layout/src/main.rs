@@ -1,38 +1,125 @@
 use std::net::{TcpListener, TcpStream};
-use std::io::prelude::*;
-use std::sync::{Arc, RwLock};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::io::{prelude::*, BufReader};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use gauge::house_layout::house::House;
-use http::HttpRequest;
+use http::{pool::ThreadPool, router::Router, HttpRequest};
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 pub(crate) type Error = http::Error;
-pub(crate) type App = Arc<RwLock<AppState>>;
+pub(crate) type App = Arc<Shared>;
 
+/// Сколько соединение может простаивать между запросами, если не задан `KEEP_ALIVE_TIMEOUT`
+const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Сколько соединений может ждать свободный поток, если не задан `QUEUE_LIMIT`
+const DEFAULT_QUEUE_LIMIT: usize = 64;
+/// Куда сохраняется дом, если не задан `DATA_DIR`
+const DEFAULT_DATA_DIR: &str = "data";
+
+mod journal;
 mod routes;
+mod storage;
+
+/// Состояние приложения и блокировка изменений, общие для всех потоков
+pub(crate) struct Shared {
+    state: RwLock<AppState>,
+    /// Изменения выполняются по одному, чтобы проверка `If-Match`,
+    /// само изменение и его сохранение не перемешались с чужими
+    writes: Mutex<()>,
+}
+
+impl Shared {
+    fn new(state: AppState) -> Self {
+        Self { state: RwLock::new(state), writes: Mutex::new(()) }
+    }
+}
 
 struct AppState {
     house: House,
+    storage: storage::Storage,
+    journal: journal::Journal,
 }
 
+impl AppState {
+    /// Записывает `entry` в журнал и сохраняет его дом на диск.
+    /// Дом в памяти меняется, только если все записалось
+    fn commit(&mut self, entry: journal::Entry) -> std::io::Result<&journal::Entry> {
+        self.storage.append(&entry)?;
+        self.storage.save(&entry.house, entry.revision)?;
 
-fn handle_stream(mut stream: TcpStream, app: App) {
-
-    let request: http::Result<HttpRequest> = (&stream)
-        .try_into()
-    ;
+        self.house = entry.house.clone();
+        self.journal.record(entry);
 
-    let responce = match request {
-        Ok(r) => {
-            match routes::route(r, app) {
-                Ok(r) => r,
-                Err(e) => routes::error_handler(e),
+        // Изменение уже сохранено, неудачное сжатие повторится при следующем
+        if self.storage.journal_len() > 2 * journal::RETENTION {
+            if let Err(e) = self.storage.compact(self.journal.entries()) {
+                eprintln!("Error compacting journal: {e}");
             }
+        }
+
+        Ok(self.journal.entries().last().expect("entry was just recorded"))
+    }
+}
+
+
+/// Обрабатывает запросы на одном соединении по порядку, пока клиент
+/// не пришлет `Connection: close` или соединение не простоит дольше `keep_alive`
+fn handle_stream(mut stream: TcpStream, app: &App, router: &Router<App>, keep_alive: Duration) {
+    if stream.set_read_timeout(Some(keep_alive)).is_err() {
+        return;
+    }
+
+    let mut reader = match stream.try_clone() {
+        Ok(s) => BufReader::new(s),
+        Err(e) => {
+            eprintln!("Error cloning stream: {e}");
+            return;
         },
-        Err(e) => routes::error_handler(e),
     };
 
-    stream.write_all(&responce.serialize().as_bytes()).expect("Error writing stream");
+    loop {
+        // Клиент закрыл соединение или ничего не прислал за `keep_alive`
+        match reader.fill_buf() {
+            Ok(buf) if !buf.is_empty() => (),
+            _ => return,
+        }
+
+        let request: http::Result<HttpRequest> = HttpRequest::read(
+            &mut reader,
+            http::DEFAULT_CONTENT_LIMIT
+        );
+
+        let keep_alive = request
+            .as_ref()
+            .is_ok_and(|r| r.keep_alive())
+            && !http::shutdown::requested();
+
+        let mut responce = match request {
+            Ok(r) => {
+                match router.handle(r, app) {
+                    Ok(r) => routes::describe(r),
+                    Err(e) => routes::error_handler(e),
+                }
+            },
+            Err(e) => routes::error_handler(e),
+        };
+
+        if !keep_alive {
+            responce.set_header("Connection".to_string(), "close".to_string());
+        }
+
+        if let Err(e) = responce.write_to(&mut stream) {
+            eprintln!("Error writing stream: {e}");
+            return;
+        }
+
+        if !keep_alive {
+            return;
+        }
+    }
 }
 
 
@@ -41,14 +128,67 @@ fn main() {
     let listener = TcpListener::bind("127.0.0.1:9000")
         .expect("Error binding TCP stream");
 
-    let app = Arc::new(RwLock::new(
-        AppState {
-            house: House::default()
-        }
-    ));
-   
-    for stream in listener.incoming() {
-        let app = Arc::clone(&app);
-        handle_stream(stream.unwrap(),app);
+    let keep_alive = match std::env::var("KEEP_ALIVE_TIMEOUT") {
+        Ok(secs) => Duration::from_secs(
+            secs.parse().expect("KEEP_ALIVE_TIMEOUT must be a number of seconds")
+        ),
+        Err(_) => DEFAULT_KEEP_ALIVE_TIMEOUT,
+    };
+
+    let workers = match std::env::var("WORKERS") {
+        Ok(workers) => workers.parse().expect("WORKERS must be a number"),
+        Err(_) => std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(4),
+    };
+
+    let queue_limit = match std::env::var("QUEUE_LIMIT") {
+        Ok(limit) => limit.parse().expect("QUEUE_LIMIT must be a number"),
+        Err(_) => DEFAULT_QUEUE_LIMIT,
+    };
+
+    let data_dir = match std::env::var("DATA_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(DEFAULT_DATA_DIR),
+    };
+
+    let mut storage = storage::Storage::new(data_dir)
+        .expect("Error creating data directory");
+
+    // Поврежденные снимки нельзя затирать пустым домом
+    let (house, revision) = storage
+        .load()
+        .expect("Error loading house, restore or remove the snapshot in DATA_DIR")
+        .unwrap_or_default();
+
+    let journal = journal::Journal::replay(storage.load_journal());
+
+    let mut state = AppState {
+        house,
+        storage,
+        journal,
     };
+
+    // Снимок мог быть записан без журнала, например до его появления,
+    // или журнал мог обогнать снимок, который не удалось сохранить
+    if revision.is_none() || state.journal.revision() != revision {
+        let entry = state.journal.prepare("load", "", state.house.clone());
+        state.commit(entry)
+            .expect("Error writing journal");
+    }
+
+    let app = Arc::new(Shared::new(state));
+
+    let router = routes::router();
+
+    let pool = ThreadPool::new(workers, queue_limit, move |stream: TcpStream| {
+        handle_stream(stream, &app, &router, keep_alive);
+    });
+
+    http::shutdown::listen();
+
+    pool.serve(&listener)
+        .expect("Error serving connections");
+
+    println!("Server stopped");
 }
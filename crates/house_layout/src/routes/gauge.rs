@@ -0,0 +1,162 @@
+use gauge::{house_layout::house::House, types::{device::DeviceId, GaugeJson}};
+use http::{router::Params, HttpRequest, HttpResponce, Status};
+use json_minimal::Json;
+
+use crate::App;
+
+use super::{app_read, array_responce, conflict, created, house::{room, room_mut}, invalid, json_content, json_responce, no_content, not_found};
+
+/// Одно устройство нельзя установить на место нескольких датчиков, иначе 409
+pub(super) fn check_devices(house: &House) -> crate::Result<()> {
+    match house.duplicate_device() {
+        Some(device) => Err(conflict(&format!("device {device} is attached to more than one gauge"))),
+        None => Ok(()),
+    }
+}
+
+/// Этаж, комната и номер датчика с устройством `:device`
+fn locate(house: &House, params: &Params) -> crate::Result<(usize, usize, usize)> {
+    house
+        .locate_device(&params.get::<DeviceId>("device")?)
+        .ok_or_else(not_found)
+}
+
+pub(super) fn get_gauges(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let gauges: Vec<Json> = room(&app.house, &params)?
+        .gauges
+        .iter()
+        .map(|g| g.clone().json())
+        .collect()
+    ;
+
+    Ok(array_responce(gauges))
+}
+
+pub(super) fn get_gauge(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let gauge = room(&app.house, &params)?
+        .gauges
+        .get(params.get::<usize>("gauge")?)
+        .ok_or_else(not_found)?
+    ;
+
+    Ok(json_responce(Status::OK, gauge.clone().json()))
+}
+
+/// Имя датчика должно быть уникальным в пределах комнаты, а `device` -
+/// в пределах дома, иначе 409. Датчик с `position` вне комнаты отвергается с 422
+pub(super) fn add_gauge(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let gauge = GaugeJson::try_from(&json_content(&request)?)
+        .map_err(invalid)?;
+
+    if gauge.device().is_some_and(|device| house.locate_device(&device).is_some()) {
+        return Err(conflict("device is already attached to another gauge"));
+    }
+
+    let room = room_mut(house, &params)?;
+    room.check_placement(&gauge)
+        .map_err(|e| invalid(e.into()))?;
+
+    let gauges = &mut room.gauges;
+
+    if gauges.iter().any(|g| g.name() == gauge.name()) {
+        return Err(conflict("gauge with this name already exists in the room"));
+    }
+    gauges.push(gauge.clone());
+
+    Ok(created(&request, gauges.len() - 1, gauge.json()))
+}
+
+/// Перемещает датчик, если указана `position` (см. [`add_gauge`])
+pub(super) fn patch_gauge(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let content = json_content(&request)?;
+
+    let location = (params.get("floor")?, params.get("room")?, params.get("gauge")?);
+
+    patch(house, location, &content)
+}
+
+/// Датчик с устройством `:device` вместе с тем, где он установлен
+pub(super) fn get_device(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let (floor, room, gauge) = locate(&app.house, &params)?;
+
+    let location = |name: &str, idx: usize| Json::OBJECT {
+        name: name.to_string(),
+        value: Box::new(Json::NUMBER(idx as f64))
+    };
+    let content = Json::JSON(vec![
+        location("floor", floor),
+        location("room", room),
+        location("index", gauge),
+        Json::OBJECT {
+            name: "gauge".to_string(),
+            value: Box::new(app.house.floors()[floor].rooms()[room].gauges[gauge].clone().json())
+        },
+    ]);
+
+    Ok(json_responce(Status::OK, content))
+}
+
+/// Обновляет датчик по устройству, не зная, где он установлен (см. [`patch_gauge`])
+pub(super) fn patch_device(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let content = json_content(&request)?;
+
+    let location = locate(house, &params)?;
+
+    patch(house, location, &content)
+}
+
+fn patch(house: &mut House, location: (usize, usize, usize), content: &Json) -> crate::Result<HttpResponce> {
+    let (floor, room, idx) = location;
+    let target = house
+        .floor(floor)
+        .and_then(|f| f.room(room))
+        .ok_or_else(not_found)?;
+    let gauges = &target.gauges;
+
+    let mut patched = gauges
+        .get(idx)
+        .ok_or_else(not_found)?
+        .clone()
+    ;
+    patched.patch(content).map_err(invalid)?;
+    target.check_placement(&patched)
+        .map_err(|e| invalid(e.into()))?;
+
+    let name_taken = gauges
+        .iter()
+        .enumerate()
+        .any(|(i, g)| i != idx && g.name() == patched.name());
+    if name_taken {
+        return Err(conflict("gauge with this name already exists in the room"));
+    }
+
+    let device_taken = patched
+        .device()
+        .and_then(|device| house.locate_device(&device))
+        .is_some_and(|other| other != location);
+    if device_taken {
+        return Err(conflict("device is already attached to another gauge"));
+    }
+
+    house.floor_mut(floor)
+        .and_then(|f| f.room_mut(room))
+        .ok_or_else(not_found)?
+        .gauges[idx] = patched.clone();
+
+    Ok(json_responce(Status::OK, patched.json()))
+}
+
+pub(super) fn delete_gauge(_request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let gauge: usize = params.get("gauge")?;
+
+    let gauges = &mut room_mut(house, &params)?.gauges;
+    if gauge >= gauges.len() {
+        return Err(not_found());
+    }
+    gauges.remove(gauge);
+
+    Ok(no_content())
+}
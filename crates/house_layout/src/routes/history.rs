@@ -0,0 +1,64 @@
+use http::{router::Params, HttpRequest, HttpResponce, Status};
+use json_minimal::Json;
+
+use crate::App;
+
+use super::{app_read, app_write, conflict, json_responce};
+
+/// Журнал изменений без снимков дома
+pub(super) fn get(_request: HttpRequest, _params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let state = app_read(app)?;
+
+    let entries: Vec<Json> = state.journal
+        .entries()
+        .iter()
+        .map(|e| e.summary())
+        .collect()
+    ;
+
+    let mut content = vec![
+        Json::OBJECT {
+            name: "history".to_string(),
+            value: Box::new(Json::ARRAY(entries))
+        }
+    ];
+
+    if let Some(revision) = state.journal.revision() {
+        content.push(Json::OBJECT {
+            name: "revision".to_string(),
+            value: Box::new(Json::NUMBER(revision as f64))
+        });
+    }
+
+    Ok(json_responce(Status::OK, Json::JSON(content)))
+}
+
+/// 409, если отменять нечего
+pub(super) fn undo(request: HttpRequest, _params: Params, app: &App) -> crate::Result<HttpResponce> {
+    restore(request, app, "nothing to undo", |journal, path| journal.prepare_undo(path))
+}
+
+/// 409, если повторять нечего
+pub(super) fn redo(request: HttpRequest, _params: Params, app: &App) -> crate::Result<HttpResponce> {
+    restore(request, app, "nothing to redo", |journal, path| journal.prepare_redo(path))
+}
+
+fn restore(
+    request: HttpRequest,
+    app: &App,
+    nothing: &str,
+    step: impl FnOnce(&crate::journal::Journal, &str) -> Option<crate::journal::Entry>
+) -> crate::Result<HttpResponce> {
+    let mut state = app_write(app)?;
+
+    let entry = step(&state.journal, request.path())
+        .ok_or_else(|| conflict(nothing))?;
+
+    let content: Json = state
+        .commit(entry)
+        .map_err(|e| http::Error::InternalServerError(e.to_string()))?
+        .clone()
+        .into();
+
+    Ok(json_responce(Status::OK, content))
+}
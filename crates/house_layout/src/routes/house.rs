@@ -1,140 +1,288 @@
-use std::{borrow::BorrowMut, ops::DerefMut};
-
-use gauge::house_layout::{floor::{self, Floor}, room::Room};
-use http::{HttpRequestBuilder, HttpResponce, HttpResponceBuilder, Status};
+use gauge::house_layout::{evacuation::EvacuationPlan, floor::Floor, graph::RoomGraph, house::House, room::Room, svg};
+use http::{router::Params, HttpRequest, HttpResponce, HttpResponceBuilder, Status};
 use json_minimal::Json;
 
-use crate::AppState;
+use crate::App;
 
-use super::RouteHandler;
+use super::{gauge::check_devices, Problem, app_read, array_responce, conflict, created, invalid, json_content, json_responce, no_content, not_found};
 
-fn app_write(args: &super::RouteArgs) -> crate::Result<&mut AppState> {
-    let mut app = args.app
-        .write()
-        .map_err(|e|
-            http::Error::InternalServerError(e.to_string().into())
-        )?
-    ;
-    Ok(&mut app)
+pub(super) fn floor<'a>(house: &'a House, params: &Params) -> crate::Result<&'a Floor> {
+    house
+        .floor(params.get("floor")?)
+        .ok_or_else(not_found)
 }
 
-fn app_read(args: &super::RouteArgs) -> crate::Result<&AppState> {
-    todo!()
+pub(super) fn floor_mut<'a>(house: &'a mut House, params: &Params) -> crate::Result<&'a mut Floor> {
+    house
+        .floor_mut(params.get("floor")?)
+        .ok_or_else(not_found)
 }
 
-pub(super) struct House {}
+pub(super) fn room<'a>(house: &'a House, params: &Params) -> crate::Result<&'a Room> {
+    floor(house, params)?
+        .room(params.get("room")?)
+        .ok_or_else(not_found)
+}
 
-impl RouteHandler for House {
-    fn handle(args: super::RouteArgs) -> crate::Result<HttpResponce> {
-        match args.request.method() {
-            http::Method::GET    => get(args),
-            http::Method::POST   => post(args),
-            http::Method::DELETE => delete(args),
-            http::Method::PATCH  => patch(args),
-            _ => Ok(
-                HttpResponceBuilder::default()
-                    .status(http::Status::MethodNotAllowed)
-                    .build()
-            ),
-        }
-    }
+pub(super) fn room_mut<'a>(house: &'a mut House, params: &Params) -> crate::Result<&'a mut Room> {
+    let room = params.get("room")?;
+
+    floor_mut(house, params)?
+        .room_mut(room)
+        .ok_or_else(not_found)
 }
 
-fn get(args: super::RouteArgs) -> crate::Result<HttpResponce> {
-    let content: Json = args.app.read()
-        .map_err(|e|
-            http::Error::InternalServerError(e.to_string().into())
-        )?
-        .house
+// HOUSE
+
+/// С параметром `revision` отдает дом из журнала изменений
+pub(super) fn get(request: HttpRequest, _params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let state = app_read(app)?;
+
+    let house = match request.query("revision") {
+        Some(revision) => {
+            let revision: u64 = revision
+                .parse()
+                .map_err(|_|
+                    Problem::new(Status::BadRequest).detail("revision expected to be a number")
+                )?;
+
+            &state.journal
+                .get(revision)
+                .ok_or_else(not_found)?
+                .house
+        },
+        None => &state.house,
+    };
+
+    let content: Json = house
         .clone()
         .into()
     ;
     let content = Json::JSON(vec![content]);
 
-    let responce = HttpResponceBuilder::default()
-        .header("Content-Type".to_string(),"application/json".to_string())
-        .content( &content.print() )
-        .build()
-    ;
+    Ok(json_responce(Status::OK, content))
+}
 
-    Ok(responce)
+/// Заменяет дом целиком, содержание в том же виде, что отдает [`get`]
+pub(super) fn put(request: HttpRequest, _params: Params, current: &mut House) -> crate::Result<HttpResponce> {
+    let house = House::try_from(&json_content(&request)?)
+        .map_err(invalid)?;
+    house.validate().map_err(invalid)?;
+    check_devices(&house)?;
+
+    *current = house.clone();
+
+    let content: Json = house.into();
+
+    Ok(json_responce(Status::OK, Json::JSON(vec![content])))
 }
 
-// POST
+// FLOOR
 
-fn post(args: super::RouteArgs) -> crate::Result<HttpResponce> {
-    let routes_str: Vec<&str> = args.route[2..]
-        .into_iter()
-        .map(|s| s.as_str())
+pub(super) fn get_floors(_request: HttpRequest, _params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let floors: Vec<Json> = app_read(app)?
+        .house
+        .floors()
+        .iter()
+        .map(|f| f.clone().into())
         .collect()
     ;
 
-    let responce = HttpResponceBuilder::default();
+    Ok(array_responce(floors))
+}
 
-    let status: Status = match *routes_str {
-        ["floor"] => {
-            app_write(&args)?
-                .house
-                .push_floor()
-            ;
+pub(super) fn get_floor(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let floor = floor(&app.house, &params)?;
 
-            Status::OK
-        },
-        ["floor",floor,"room"] => {
-            let floor_number: usize = 
-                floor.parse()
-                .map_err(|_| http::Error::MalformedRequest)?
-            ;
+    Ok(json_responce(Status::OK, floor.clone().into()))
+}
 
-            let floor = app_write(&args)?
-                .house
-                .floors()
-                .get(floor_number as usize)
-                .ok_or(http::Error::ResponceError(
-                    HttpResponceBuilder::default()
-                        .status(Status::NotFound)
-                        .build()
-                ))?
-            ;
-
-            let content = args.request.content();
-
-            let room: Room = content
-                .map(|r| -> crate::Result<Room> {
-                    Json::parse(r.as_bytes())
-                        .map_err(|_|
-                            http::Error::MalformedRequest
-                        )?
-                    .try_into()?
-                })?
-                .unwrap_or_default()
-            ;
-
-            floor.add_room(room)
-
-            Status::OK
+pub(super) fn add_floor(request: HttpRequest, _params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let floor = house
+        .push_floor()
+        .ok_or_else(|| conflict("no floor number left above the top floor"))?
+        .clone();
+
+    Ok(created(&request, house.floors().len() - 1, floor.into()))
+}
+
+pub(super) fn patch_floor(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let content = json_content(&request)?;
+    let idx: usize = params.get("floor")?;
+
+    let mut patched = floor(house, &params)?.clone();
+    patched.patch(&content).map_err(invalid)?;
+
+    let number_taken = house
+        .floors()
+        .iter()
+        .enumerate()
+        .any(|(i, f)| i != idx && f.number() == patched.number());
+    if number_taken {
+        return Err(conflict("floor with this number already exists"));
+    }
+
+    *floor_mut(house, &params)? = patched.clone();
+
+    Ok(json_responce(Status::OK, patched.into()))
+}
+
+pub(super) fn delete_floor(_request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    house
+        .remove_floor(params.get("floor")?)
+        .ok_or_else(not_found)?
+    ;
+
+    Ok(no_content())
+}
+
+/// Граф смежности комнат этажа. С параметром `room`
+/// добавляет комнаты, в которые из нее можно пройти
+pub(super) fn get_graph(request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let floor = floor(&app.house, &params)?;
+    let graph = RoomGraph::from_floor(floor);
+
+    let reachable = match request.query("room") {
+        Some(room) => {
+            let room: usize = room
+                .parse()
+                .map_err(|_|
+                    Problem::new(Status::BadRequest).detail("room expected to be a number")
+                )?;
+
+            if floor.room(room).is_none() {
+                return Err(not_found());
+            }
+
+            Some(graph.reachable(room))
         },
-        _ => Status::NotFound
+        None => None,
     };
 
-    responce = responce.status(status);
-    let responce = responce.build();
+    let mut content: Json = graph.into();
+
+    if let (Json::JSON(fields), Some(reachable)) = (&mut content, reachable) {
+        fields.push(Json::OBJECT {
+            name: "reachable".to_string(),
+            value: Box::new(Json::ARRAY(
+                reachable
+                    .into_iter()
+                    .map(|r| Json::NUMBER(r as f64))
+                    .collect()
+            ))
+        });
+    }
+
+    Ok(json_responce(Status::OK, content))
+}
+
+/// Пути эвакуации из каждой комнаты этажа в обход комнат с тревогой
+pub(super) fn get_evacuation(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let plan = EvacuationPlan::for_floor(floor(&app.house, &params)?);
 
-    Ok(responce)
+    Ok(json_responce(Status::OK, plan.into()))
 }
 
-fn add_floor(args: super::RouteArgs) -> crate::Result<HttpResponce> {
+/// План этажа в SVG для просмотра в браузере
+pub(super) fn get_plan(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let plan = svg::render(floor(&app.house, &params)?);
+
     Ok(
         HttpResponceBuilder::default()
-            .content(&"Aboba")
+            .status(Status::OK)
+            .header("Content-Type".to_string(), "image/svg+xml".to_string())
+            .content(&plan)
             .build()
     )
 }
 
-fn patch(args: super::RouteArgs) -> crate::Result<HttpResponce> {
-    todo!()
+// ROOM
+
+pub(super) fn get_rooms(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let rooms: Vec<Json> = floor(&app.house, &params)?
+        .rooms()
+        .iter()
+        .map(|r| r.clone().into())
+        .collect()
+    ;
+
+    Ok(array_responce(rooms))
+}
+
+pub(super) fn get_room(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let room = room(&app.house, &params)?;
+
+    Ok(json_responce(Status::OK, room.clone().into()))
+}
+
+pub(super) fn add_room(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let room: Room = match request.content() {
+        Some(_) => Room::try_from(&json_content(&request)?)
+            .map_err(invalid)?,
+        // Без содержания - еще не размеченная комната без стен
+        None => Room { walls: vec![], ..Room::default() },
+    };
+    room.validate().map_err(invalid)?;
+
+    let floor = floor_mut(house, &params)?;
+    floor.add_room(room.clone());
+    let idx = floor.rooms().len() - 1;
+
+    check_devices(house)?;
+
+    Ok(created(&request, idx, room.into()))
+}
+
+pub(super) fn patch_room(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let content = json_content(&request)?;
+
+    let room = room_mut(house, &params)?;
+
+    let mut patched = room.clone();
+    patched.patch(&content).map_err(invalid)?;
+    patched.validate().map_err(invalid)?;
+    *room = patched.clone();
+
+    check_devices(house)?;
+
+    Ok(json_responce(Status::OK, patched.into()))
 }
 
-fn delete(args: super::RouteArgs) -> crate::Result<HttpResponce> {
-    todo!()
+/// Многоугольник комнаты с площадью, периметром и объемом, 422 для некорректных стен
+pub(super) fn get_geometry(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let room = room(&app.house, &params)?;
+
+    room.validate().map_err(invalid)?;
+    let polygon = room.polygon()
+        .map_err(|e| invalid(e.into()))?;
+
+    let volume = polygon.volume(room.height);
+    let mut content: Json = polygon.into();
+
+    if let Json::JSON(fields) = &mut content {
+        fields.push(Json::OBJECT {
+            name: "volume".to_string(),
+            value: Box::new(Json::NUMBER(volume))
+        });
+    }
+
+    Ok(json_responce(Status::OK, content))
+}
+
+pub(super) fn delete_room(_request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let room = params.get("room")?;
+
+    floor_mut(house, &params)?
+        .remove_room(room)
+        .ok_or_else(not_found)?
+    ;
+
+    Ok(no_content())
 }
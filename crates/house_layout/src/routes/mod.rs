@@ -1,66 +1,521 @@
-use std::fmt::Debug;
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
 
-use house::House;
-use http::{HttpRequest, HttpResponce, HttpResponceBuilder, Status};
+use http::{router::{Params, Router}, HttpRequest, HttpResponce, HttpResponceBuilder, Method, Status};
+use ::gauge::{helpers::JsonError, house_layout::house::House};
+use json_minimal::Json;
 
-use crate::{App, Result};
+use crate::{App, AppState};
 
+pub(crate) use problem::{describe, Problem};
+
+mod history;
 mod house;
+mod problem;
+mod wall;
+mod gauge;
+
+pub(crate) fn router() -> Router<App> {
+    Router::default()
+        .route(Method::GET, "/", |_, _, _|
+            Ok(HttpResponceBuilder::default().build())
+        )
+        .route(Method::GET,    "/house", cached_revision(house::get))
+        .route(Method::PUT,    "/house", persist("replace house", house::put))
+        .route(Method::GET,    "/house/history", cached(history::get))
+        .route(Method::POST,   "/house/undo", guarded(history::undo))
+        .route(Method::POST,   "/house/redo", guarded(history::redo))
+
+        .route(Method::GET,    "/house/floor", cached(house::get_floors))
+        .route(Method::POST,   "/house/floor", persist("add floor", house::add_floor))
+        .route(Method::GET,    "/house/floor/:floor.svg", cached(house::get_plan))
+        .route(Method::GET,    "/house/floor/:floor", cached(house::get_floor))
+        .route(Method::PATCH,  "/house/floor/:floor", persist("update floor", house::patch_floor))
+        .route(Method::DELETE, "/house/floor/:floor", persist("delete floor", house::delete_floor))
+
+        .route(Method::GET,    "/house/floor/:floor/graph", cached(house::get_graph))
+        .route(Method::GET,    "/house/floor/:floor/evacuation", cached(house::get_evacuation))
+
+        .route(Method::GET,    "/house/floor/:floor/room", cached(house::get_rooms))
+        .route(Method::POST,   "/house/floor/:floor/room", persist("add room", house::add_room))
+        .route(Method::GET,    "/house/floor/:floor/room/:room", cached(house::get_room))
+        .route(Method::PATCH,  "/house/floor/:floor/room/:room", persist("update room", house::patch_room))
+        .route(Method::DELETE, "/house/floor/:floor/room/:room", persist("delete room", house::delete_room))
+
+        .route(Method::GET,    "/house/floor/:floor/room/:room/geometry", cached(house::get_geometry))
+
+        .route(Method::GET,    "/house/floor/:floor/room/:room/wall", cached(wall::get_walls))
+        .route(Method::POST,   "/house/floor/:floor/room/:room/wall", persist("add wall", wall::add_wall))
+        .route(Method::GET,    "/house/floor/:floor/room/:room/wall/:wall", cached(wall::get_wall))
+        .route(Method::PATCH,  "/house/floor/:floor/room/:room/wall/:wall", persist("move wall", wall::patch_wall))
+        .route(Method::DELETE, "/house/floor/:floor/room/:room/wall/:wall", persist("delete wall", wall::delete_wall))
+
+        .route(Method::GET,    "/house/floor/:floor/room/:room/wall/:wall/door", cached(wall::get_doors))
+        .route(Method::POST,   "/house/floor/:floor/room/:room/wall/:wall/door", persist("add door", wall::add_door))
+        .route(Method::GET,    "/house/floor/:floor/room/:room/wall/:wall/door/:door", cached(wall::get_door))
+        .route(Method::PATCH,  "/house/floor/:floor/room/:room/wall/:wall/door/:door", persist("move door", wall::patch_door))
+        .route(Method::DELETE, "/house/floor/:floor/room/:room/wall/:wall/door/:door", persist("delete door", wall::delete_door))
 
-struct RouteArgs {
-    request: HttpRequest,
-    app: App,
-    route: Vec<String>
+        .route(Method::GET,    "/house/floor/:floor/room/:room/gauge", cached(gauge::get_gauges))
+        .route(Method::POST,   "/house/floor/:floor/room/:room/gauge", persist("attach gauge", gauge::add_gauge))
+        .route(Method::GET,    "/house/floor/:floor/room/:room/gauge/:gauge", cached(gauge::get_gauge))
+        .route(Method::PATCH,  "/house/floor/:floor/room/:room/gauge/:gauge", persist("update gauge", gauge::patch_gauge))
+        .route(Method::DELETE, "/house/floor/:floor/room/:room/gauge/:gauge", persist("detach gauge", gauge::delete_gauge))
+
+        .route(Method::GET,    "/house/device/:device", cached(gauge::get_device))
+        .route(Method::PATCH,  "/house/device/:device", persist("update gauge", gauge::patch_device))
+}
+
+/// ETag дома - его ревизия в журнале, поэтому он меняется при любом изменении
+fn etag(app: &App) -> crate::Result<String> {
+    let revision = app_read(app)?
+        .journal
+        .revision()
+        .unwrap_or_default();
+
+    Ok(format!("\"{revision}\""))
 }
 
-trait RouteHandler {
-    fn handle(args: RouteArgs) -> Result<HttpResponce>;
+/// Есть ли `etag` среди значений заголовков `If-Match` или `If-None-Match`.
+/// `weak` разрешает сравнивать со слабыми тегами `W/"..."`
+fn etag_matches<'a>(mut values: impl Iterator<Item = &'a str>, etag: &str, weak: bool) -> bool {
+    values.any(|value|
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| {
+                let tag = match tag.strip_prefix("W/") {
+                    Some(tag) if weak => tag,
+                    Some(_) => return false,
+                    None => tag,
+                };
+
+                tag == "*" || tag == etag
+            })
+    )
+}
+
+/// Добавляет ETag к ответу и отвечает 304, если он совпал с `If-None-Match`
+fn cached<H>(handler: H) -> impl Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+where
+    H: Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+{
+    conditional(false, handler)
 }
 
-pub(crate) fn route(request: HttpRequest, app: App) -> Result<HttpResponce> {
-    let route = request
-        .path()
-        .split("/")
-        .map(|s| s.to_owned())
-        .collect()
+/// Как [`cached`], но для дома из журнала (параметр запроса `revision`)
+/// ETag - номер запрошенной ревизии: она уже не меняется
+fn cached_revision<H>(handler: H) -> impl Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+where
+    H: Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+{
+    conditional(true, handler)
+}
+
+fn conditional<H>(by_revision: bool, handler: H) -> impl Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+where
+    H: Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+{
+    move |request, params, app| {
+        let revision: Option<u64> = request
+            .query("revision")
+            .filter(|_| by_revision)
+            .and_then(|r| r.parse().ok());
+        let current = || match revision {
+            Some(revision) => Ok(format!("\"{revision}\"")),
+            None => etag(app),
+        };
+
+        let before = current()?;
+        let not_modified = etag_matches(request.headers().get_all("If-None-Match"), &before, true);
+
+        let mut responce = handler(request, params, app)?;
+
+        // Дом изменился, пока собирался ответ, и ревизия ему уже не соответствует
+        if current()? != before || !responce.status().is_success() {
+            return Ok(responce);
+        }
+
+        if not_modified {
+            responce = HttpResponceBuilder::default()
+                .status(Status::NotModified)
+                .build();
+        }
+
+        responce.set_header("ETag".to_string(), before);
+
+        Ok(responce)
+    }
+}
+
+/// Выполняет изменение, только если `If-Match` совпал с текущим ETag,
+/// иначе отвечает 412. К ответу добавляется новый ETag.
+/// Блокировка изменений приложения держится от проверки до сохранения на диск
+fn guarded<H>(handler: H) -> impl Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+where
+    H: Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+{
+    move |request, params, app| {
+        let _write = app.writes
+            .lock()
+            .map_err(|e| http::Error::InternalServerError(e.to_string()))?;
+
+        let headers = request.headers();
+
+        if headers.contains("If-Match") && !etag_matches(headers.get_all("If-Match"), &etag(app)?, false) {
+            Err(error_responce(Status::PreconditionFailed))?
+        }
+
+        let mut responce = handler(request, params, app)?;
+
+        if responce.status().is_success() {
+            responce.set_header("ETag".to_string(), etag(app)?);
+        }
+
+        Ok(responce)
+    }
+}
+
+/// Выполняет изменение на копии дома, записывает его в журнал как `action`
+/// и сохраняет на диск. Дом в памяти заменяется копией, только если
+/// изменение успешно и сохранено. Изменение проверяет `If-Match` (см. [`guarded`])
+fn persist<H>(action: &'static str, handler: H) -> impl Fn(HttpRequest, Params, &App) -> crate::Result<HttpResponce>
+where
+    H: Fn(HttpRequest, Params, &mut House) -> crate::Result<HttpResponce>
+{
+    guarded(move |request, params, app| {
+        let path = request.path().to_string();
+        let mut house = app_read(app)?.house.clone();
+
+        let responce = handler(request, params, &mut house)?;
+
+        if responce.status().is_success() {
+            let mut state = app_write(app)?;
+            let entry = state.journal.prepare(action, &path, house);
+
+            state
+                .commit(entry)
+                .map_err(|e| http::Error::InternalServerError(e.to_string()))?;
+        }
+
+        Ok(responce)
+    })
+}
+
+fn app_write(app: &App) -> crate::Result<RwLockWriteGuard<'_, AppState>> {
+    app.state
+        .write()
+        .map_err(|e|
+            http::Error::InternalServerError(e.to_string())
+        )
+}
+
+fn app_read(app: &App) -> crate::Result<RwLockReadGuard<'_, AppState>> {
+    app.state
+        .read()
+        .map_err(|e|
+            http::Error::InternalServerError(e.to_string())
+        )
+}
+
+fn error_responce(status: Status) -> http::Error {
+    Problem::new(status).into()
+}
+
+fn not_found() -> http::Error {
+    error_responce(Status::NotFound)
+}
+
+fn conflict(detail: &str) -> http::Error {
+    Problem::new(Status::Conflict)
+        .detail(detail)
+        .into()
+}
+
+/// Ошибка разбора модели из содержания запроса, 422 с сообщением
+/// и путем до поля, если он известен (см. [`JsonError`])
+fn invalid(e: Box<dyn std::error::Error>) -> http::Error {
+    let problem = Problem::new(Status::UnprocessableContent)
+        .kind(problem::VALIDATION);
+
+    match e.downcast_ref::<JsonError>() {
+        Some(e) if !e.path().is_empty() => problem.detail(e.message()).field(e.path()),
+        Some(e) => problem.detail(e.message()),
+        None => problem.detail(e),
+    }
+        .into()
+}
+
+/// Содержание запроса в виде JSON, обязательно для POST и PATCH
+fn json_content(request: &HttpRequest) -> crate::Result<Json> {
+    let content = request
+        .content()
+        .as_ref()
+        .ok_or_else(||
+            Problem::new(Status::BadRequest).detail("request content required")
+        )?
     ;
 
-    let args = RouteArgs {
-        request,
-        app,
-        route
-    };
+    Json::parse(content.as_bytes())
+        .map_err(|(idx, msg)|
+            Problem::new(Status::BadRequest)
+                .detail(format!("malformed json at {idx}: {msg}"))
+                .into()
+        )
+}
 
-    let responce = match args.route[1].as_str() {
-        "" =>
-            HttpResponceBuilder::default().build(),
-        "house" =>
-            House::handle(args)?,
-        _ =>
-            HttpResponceBuilder::default()
-                .status(Status::NotFound)
-                .build(),
-    };
+fn json_responce(status: Status, content: Json) -> HttpResponce {
+    HttpResponceBuilder::default()
+        .status(status)
+        .header("Content-Type".to_string(),"application/json".to_string())
+        .content( &content.print() )
+        .build()
+}
+
+fn array_responce(content: Vec<Json>) -> HttpResponce {
+    json_responce(Status::OK, Json::ARRAY(content))
+}
 
-    Ok(responce)
+/// 201 с адресом нового элемента коллекции `request`
+fn created(request: &HttpRequest, idx: usize, content: Json) -> HttpResponce {
+    let mut responce = json_responce(Status::Created, content);
+    responce.set_header(
+        "Location".to_string(),
+        format!("{}/{idx}", request.path().trim_end_matches('/'))
+    );
+
+    responce
+}
+
+fn no_content() -> HttpResponce {
+    HttpResponceBuilder::default()
+        .status(Status::NoContent)
+        .build()
 }
 
 
 pub(crate) fn error_handler(e: http::Error) -> HttpResponce {
-    let status: Status = match e {
-        http::Error::MalformedStatusLine => Status::BadRequest,
-        http::Error::MalformedRequest => Status::BadRequest,
-        http::Error::UnknownRequestMethod => Status::BadRequest,
-        http::Error::UnsopportedProtocol => Status::BadRequest,
-        http::Error::MalformedHeader => Status::BadRequest,
-        http::Error::ResponceError(e) => return e,
+    let problem = match e {
+        http::Error::MalformedStatusLine => Problem::new(Status::BadRequest),
+        http::Error::MalformedRequest => Problem::new(Status::BadRequest),
+        http::Error::UnknownRequestMethod => Problem::new(Status::BadRequest)
+            .detail("unknown request method"),
+        http::Error::UnsopportedProtocol => Problem::new(Status::BadRequest)
+            .detail("unsupported protocol"),
+        http::Error::MalformedHeader => Problem::new(Status::BadRequest)
+            .detail("malformed header"),
+        http::Error::ContentTooLarge => Problem::new(Status::ContentTooLarge)
+            .detail(format!("request content is limited to {} bytes", http::DEFAULT_CONTENT_LIMIT)),
+        http::Error::ResponceError(e) => return describe(e),
         _ => {
             eprint!("{}", e);
-            Status::InternalServerError
+            Problem::new(Status::InternalServerError)
         }
     };
-    
-    HttpResponceBuilder::default()
-        .status(status)
-        .build()
+
+    problem.responce()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use ::gauge::house_layout::house::House;
+    use http::{HttpRequestBuilder, HttpResponce, Method, Status};
+    use json_minimal::Json;
+
+    use crate::{journal::Journal, storage::Storage, App, AppState, Shared};
+
+    use super::{describe, error_handler, etag_matches, router};
+
+    /// Приложение с домом по умолчанию и хранилищем во временном каталоге `name`
+    fn app(name: &str) -> (App, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("house_layout_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let app = Arc::new(Shared::new(AppState {
+            house: House::default(),
+            storage: Storage::new(&dir).unwrap(),
+            journal: Journal::default(),
+        }));
+
+        (app, dir)
+    }
+
+    /// Запрос через маршрутизатор так же, как его обрабатывает сервер
+    fn send(app: &App, method: Method, path: &str, content: Option<&str>) -> HttpResponce {
+        let mut request = HttpRequestBuilder::new()
+            .method(method)
+            .path(&path);
+
+        if let Some(content) = content {
+            request = request.content(&content);
+        }
+
+        match router().handle(request.build(), app) {
+            Ok(responce) => describe(responce),
+            Err(e) => error_handler(e),
+        }
+    }
+
+    fn content(responce: &HttpResponce) -> Json {
+        Json::parse(responce.content().as_ref().unwrap().as_bytes()).unwrap()
+    }
+
+    fn number(json: &Json, field: &str) -> f64 {
+        match json.get(field).map(|v| v.unbox()) {
+            Some(Json::NUMBER(n)) => *n,
+            other => panic!("{field} is not a number: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn old_revision_etag() {
+        let (app, dir) = app("old_revision_etag");
+
+        send(&app, Method::POST, "/house/floor", None);
+        send(&app, Method::POST, "/house/floor", None);
+
+        let get = |path: &str, etag: &str| {
+            let request = HttpRequestBuilder::new()
+                .path(&path)
+                .header("If-None-Match".to_string(), etag.to_string())
+                .build();
+
+            match router().handle(request, &app) {
+                Ok(responce) => responce,
+                Err(e) => error_handler(e),
+            }
+        };
+
+        // Текущий ETag не подходит к старой ревизии
+        let responce = get("/house?revision=0", "\"1\"");
+        assert_eq!(*responce.status(), Status::OK);
+        assert_eq!(responce.headers().get("ETag"), Some("\"0\""));
+        assert_eq!(content(&responce).get("floors").map(|f| match f.unbox() {
+            Json::ARRAY(floors) => floors.len(),
+            _ => 0,
+        }), Some(2));
+
+        assert_eq!(*get("/house?revision=0", "\"0\"").status(), Status::NotModified);
+        assert_eq!(*get("/house", "\"1\"").status(), Status::NotModified);
+        assert_eq!(*get("/house?revision=9", "\"9\"").status(), Status::NotFound);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn floor_crud() {
+        let (app, dir) = app("floor_crud");
+
+        let responce = send(&app, Method::POST, "/house/floor", None);
+        assert_eq!(*responce.status(), Status::Created);
+        assert_eq!(responce.headers().get("Location"), Some("/house/floor/1"));
+        assert_eq!(number(&content(&responce), "number"), 1.0);
+
+        let responce = send(&app, Method::PATCH, "/house/floor/1", Some(r#"{"number":5}"#));
+        assert_eq!(*responce.status(), Status::OK);
+        assert_eq!(*send(&app, Method::PATCH, "/house/floor/0", Some(r#"{"number":5}"#)).status(), Status::Conflict);
+
+        // Новый этаж над самым верхним, а не по количеству этажей
+        let responce = send(&app, Method::POST, "/house/floor", None);
+        assert_eq!(number(&content(&responce), "number"), 6.0);
+
+        assert_eq!(*send(&app, Method::DELETE, "/house/floor/1", None).status(), Status::NoContent);
+        assert_eq!(number(&content(&send(&app, Method::GET, "/house/floor/1", None)), "number"), 6.0);
+        assert_eq!(*send(&app, Method::GET, "/house/floor/2", None).status(), Status::NotFound);
+        assert_eq!(*send(&app, Method::DELETE, "/house/floor/2", None).status(), Status::NotFound);
+        assert_eq!(*send(&app, Method::PUT, "/house/floor", None).status(), Status::MethodNotAllowed);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn room_wall_door_crud() {
+        let (app, dir) = app("room_wall_door_crud");
+        let walls = r#"{"walls":[
+            {"position_start":{"x":0,"y":0},"position_end":{"x":400,"y":0}},
+            {"position_start":{"x":400,"y":0},"position_end":{"x":400,"y":300}},
+            {"position_start":{"x":400,"y":300},"position_end":{"x":0,"y":300}},
+            {"position_start":{"x":0,"y":300},"position_end":{"x":0,"y":0}}
+        ],"height":250}"#;
+
+        // Стена нулевой длины не образует комнату
+        let degenerate = r#"{"height":250,"walls":[{"position_start":{"x":0,"y":0},"position_end":{"x":0,"y":0}}]}"#;
+        let responce = send(&app, Method::POST, "/house/floor/0/room", Some(degenerate));
+        assert_eq!(*responce.status(), Status::UnprocessableContent);
+        assert!(matches!(content(&responce).get("field").map(|f| f.unbox()), Some(Json::STRING(field)) if field == "/walls/0"));
+
+        let responce = send(&app, Method::POST, "/house/floor/0/room", None);
+        assert_eq!(*responce.status(), Status::Created);
+        assert!(matches!(content(&responce).get("walls").map(|w| w.unbox()), Some(Json::ARRAY(walls)) if walls.is_empty()));
+
+        let responce = send(&app, Method::POST, "/house/floor/0/room", Some(walls));
+        assert_eq!(*responce.status(), Status::Created);
+        assert_eq!(responce.headers().get("Location"), Some("/house/floor/0/room/2"));
+
+        let room = "/house/floor/0/room/2";
+        assert_eq!(*send(&app, Method::PATCH, room, Some(r#"{"height":270}"#)).status(), Status::OK);
+        assert_eq!(*send(&app, Method::GET, &format!("{room}/geometry"), None).status(), Status::OK);
+
+        // Перенос одной стены размыкает комнату
+        let moved = r#"{"position_end":{"x":500,"y":0}}"#;
+        assert_eq!(*send(&app, Method::PATCH, &format!("{room}/wall/0"), Some(moved)).status(), Status::UnprocessableContent);
+        assert_eq!(*send(&app, Method::DELETE, &format!("{room}/wall/0"), None).status(), Status::UnprocessableContent);
+
+        let door = r#"{"position_start":{"x":100,"y":0},"position_end":{"x":180,"y":0}}"#;
+        let responce = send(&app, Method::POST, &format!("{room}/wall/0/door"), Some(door));
+        assert_eq!(*responce.status(), Status::Created);
+        assert_eq!(responce.headers().get("Location"), Some("/house/floor/0/room/2/wall/0/door/0"));
+
+        let patched = send(&app, Method::PATCH, &format!("{room}/wall/0/door/0"), Some(r#"{"exit":true}"#));
+        assert_eq!(*patched.status(), Status::OK);
+        assert!(matches!(content(&patched).get("exit").map(|v| v.unbox()), Some(Json::BOOL(true))));
+
+        // Поиск несуществующей двери не создает пустой список дверей
+        assert_eq!(*send(&app, Method::PATCH, &format!("{room}/wall/1/door/0"), Some(r#"{"exit":true}"#)).status(), Status::NotFound);
+        let wall = content(&send(&app, Method::GET, &format!("{room}/wall/1"), None));
+        assert!(matches!(wall.get("doors").map(|v| v.unbox()), Some(Json::NULL)));
+
+        assert_eq!(*send(&app, Method::DELETE, &format!("{room}/wall/0/door/0"), None).status(), Status::NoContent);
+        assert_eq!(*send(&app, Method::GET, &format!("{room}/wall/0/door/0"), None).status(), Status::NotFound);
+
+        assert_eq!(*send(&app, Method::DELETE, room, None).status(), Status::NoContent);
+        assert_eq!(*send(&app, Method::GET, room, None).status(), Status::NotFound);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn gauge_crud() {
+        let (app, dir) = app("gauge_crud");
+        let gauges = "/house/floor/0/room/0/gauge";
+
+        let existing = content(&send(&app, Method::GET, &format!("{gauges}/0"), None));
+        let Some(Json::STRING(id)) = existing.get("id").map(|v| v.unbox()) else {
+            panic!("gauge has no id");
+        };
+
+        let gauge = format!(r#"{{"id":"{id}","name":"Kitchen","state":"Disabled"}}"#);
+        let responce = send(&app, Method::POST, gauges, Some(&gauge));
+        assert_eq!(*responce.status(), Status::Created);
+        assert_eq!(*send(&app, Method::POST, gauges, Some(&gauge)).status(), Status::Conflict);
+
+        let renamed = send(&app, Method::PATCH, &format!("{gauges}/1"), Some(r#"{"name":"Hall"}"#));
+        assert_eq!(*renamed.status(), Status::OK);
+        assert_eq!(*send(&app, Method::PATCH, &format!("{gauges}/1"), Some(r#"{"name":"Test"}"#)).status(), Status::Conflict);
+
+        assert_eq!(*send(&app, Method::DELETE, &format!("{gauges}/1"), None).status(), Status::NoContent);
+        assert_eq!(*send(&app, Method::DELETE, &format!("{gauges}/1"), None).status(), Status::NotFound);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn etag_matching() {
+        assert!(etag_matches(["\"1\", \"2\""].into_iter(), "\"2\"", false));
+        assert!(etag_matches(["\"3\"", "*"].into_iter(), "\"2\"", false));
+        assert!(etag_matches(["W/\"2\""].into_iter(), "\"2\"", true));
+        assert!(!etag_matches(["W/\"2\""].into_iter(), "\"2\"", false));
+        assert!(!etag_matches(std::iter::empty(), "\"2\"", true));
+    }
 }
@@ -0,0 +1,234 @@
+//! Ошибки в формате `application/problem+json` (RFC 9457)
+//!
+//! ```json
+//! {
+//!     "type": "/problems/validation",
+//!     "title": "Unprocessable Content",
+//!     "status": 422,
+//!     "detail": "expected to be number",
+//!     "field": "/walls/0/position_start/x"
+//! }
+//! ```
+use http::{HttpResponce, HttpResponceBuilder, Status};
+use json_minimal::Json;
+
+/// Тип ошибки, когда содержание запроса не прошло проверку модели
+pub(super) const VALIDATION: &str = "/problems/validation";
+/// Тип ошибки без дополнительного смысла, кроме статуса
+const ABOUT_BLANK: &str = "about:blank";
+
+pub(crate) struct Problem {
+    status: Status,
+    kind: &'static str,
+    detail: Option<String>,
+    field: Option<String>,
+}
+
+impl Problem {
+    pub(crate) fn new(status: Status) -> Self {
+        Self { status, kind: ABOUT_BLANK, detail: None, field: None }
+    }
+
+    pub(crate) fn kind(mut self, kind: &'static str) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Объяснение, которое можно показать пользователю
+    pub(crate) fn detail(mut self, detail: impl ToString) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    /// JSON Pointer до поля содержания запроса, в котором ошибка
+    pub(crate) fn field(mut self, field: impl ToString) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    pub(crate) fn responce(self) -> HttpResponce {
+        self.builder().build()
+    }
+
+    fn builder(self) -> HttpResponceBuilder {
+        let mut content = vec![
+            Json::OBJECT {
+                name: "type".to_string(),
+                value: Box::new(Json::STRING(self.kind.to_string()))
+            },
+            Json::OBJECT {
+                name: "title".to_string(),
+                value: Box::new(Json::STRING(self.status.reason().to_string()))
+            },
+            Json::OBJECT {
+                name: "status".to_string(),
+                value: Box::new(Json::NUMBER(self.status.code().into()))
+            },
+        ];
+
+        for (name, value) in [("detail", self.detail), ("field", self.field)] {
+            if let Some(value) = value {
+                content.push(Json::OBJECT {
+                    name: name.to_string(),
+                    value: Box::new(Json::STRING(value))
+                });
+            }
+        }
+
+        HttpResponceBuilder::default()
+            .status(self.status)
+            .header("Content-Type".to_string(), "application/problem+json".to_string())
+            .content(&Json::JSON(content).print())
+    }
+}
+
+impl From<Problem> for http::Error {
+    fn from(value: Problem) -> Self {
+        http::Error::ResponceError(value.responce())
+    }
+}
+
+/// Добавляет описание ошибки к ответам с ошибкой без содержания,
+/// например к 404 и 405 от [`http::router::Router`]. Заголовки сохраняются
+pub(crate) fn describe(responce: HttpResponce) -> HttpResponce {
+    let status = responce.status();
+
+    if !(status.is_client_error() || status.is_server_error()) || responce.content().is_some() {
+        return responce;
+    }
+
+    let mut builder = Problem::new(status.clone()).builder();
+
+    for (key, value) in responce.headers().iter() {
+        if !key.eq_ignore_ascii_case("Content-Length") && !key.eq_ignore_ascii_case("Content-Type") {
+            builder = builder.header(key.to_string(), value.to_string());
+        }
+    }
+
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use ::gauge::helpers::JsonError;
+    use http::{HttpResponce, HttpResponceBuilder, Status};
+    use json_minimal::Json;
+
+    use super::{describe, Problem, VALIDATION};
+    use crate::routes::{conflict, error_handler, error_responce, invalid, not_found};
+
+    /// Содержание ответа с ошибкой после проверки типа и обязательных полей
+    fn problem(responce: &HttpResponce) -> Json {
+        assert_eq!(responce.headers().content_type(), Some("application/problem+json"));
+
+        let json = Json::parse(responce.content().as_ref().unwrap().as_bytes()).unwrap();
+        let field = |name: &str| json.get(name).map(|v| v.unbox());
+
+        assert!(matches!(field("type"), Some(Json::STRING(_))));
+        assert!(matches!(field("title"), Some(Json::STRING(title)) if title == responce.status().reason()));
+        assert!(matches!(field("status"), Some(Json::NUMBER(code)) if *code == responce.status().code() as f64));
+
+        json
+    }
+
+    fn string(json: &Json, name: &str) -> Option<String> {
+        match json.get(name).map(|v| v.unbox()) {
+            Some(Json::STRING(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn error(e: http::Error) -> (Status, Json) {
+        let responce = error_handler(e);
+        let json = problem(&responce);
+
+        (responce.status().clone(), json)
+    }
+
+    #[test]
+    fn problem_body() {
+        let responce = Problem::new(Status::Conflict)
+            .kind(VALIDATION)
+            .detail("taken")
+            .field("/name")
+            .responce();
+        let json = problem(&responce);
+
+        assert_eq!(*responce.status(), Status::Conflict);
+        assert_eq!(string(&json, "type").as_deref(), Some(VALIDATION));
+        assert_eq!(string(&json, "detail").as_deref(), Some("taken"));
+        assert_eq!(string(&json, "field").as_deref(), Some("/name"));
+
+        // Необязательные поля не выводятся
+        let json = problem(&Problem::new(Status::NotFound).responce());
+        assert_eq!(string(&json, "type").as_deref(), Some("about:blank"));
+        assert!(json.get("detail").is_none());
+        assert!(json.get("field").is_none());
+    }
+
+    #[test]
+    fn invalid_maps_path_and_detail() {
+        let (status, json) = error(invalid(JsonError::field("x", "expected to be number").within("position").into()));
+        assert_eq!(status, Status::UnprocessableContent);
+        assert_eq!(string(&json, "type").as_deref(), Some(VALIDATION));
+        assert_eq!(string(&json, "detail").as_deref(), Some("expected to be number"));
+        assert_eq!(string(&json, "field").as_deref(), Some("/position/x"));
+
+        let (_, json) = error(invalid(JsonError::new("expected to be object").into()));
+        assert_eq!(string(&json, "detail").as_deref(), Some("expected to be object"));
+        assert!(json.get("field").is_none());
+
+        let (_, json) = error(invalid("not a json model".into()));
+        assert_eq!(string(&json, "detail").as_deref(), Some("not a json model"));
+    }
+
+    #[test]
+    fn describe_empty_errors() {
+        let responce = describe(
+            HttpResponceBuilder::default()
+                .status(Status::MethodNotAllowed)
+                .header("Allow".to_string(), "GET, POST".to_string())
+                .build()
+        );
+        problem(&responce);
+        assert_eq!(responce.headers().get("Allow"), Some("GET, POST"));
+
+        // Успешные ответы и ответы с содержанием не меняются
+        let ok = describe(HttpResponceBuilder::default().build());
+        assert!(ok.content().is_none());
+
+        let plain = describe(
+            HttpResponceBuilder::default()
+                .status(Status::BadRequest)
+                .header("Content-Type".to_string(), "text/plain".to_string())
+                .content(&"plain")
+                .build()
+        );
+        assert_eq!(plain.content().as_deref(), Some("plain"));
+        assert_eq!(plain.headers().content_type(), Some("text/plain"));
+    }
+
+    #[test]
+    fn error_statuses() {
+        let cases = [
+            (http::Error::MalformedRequest, Status::BadRequest),
+            (http::Error::UnknownRequestMethod, Status::BadRequest),
+            (http::Error::MalformedHeader, Status::BadRequest),
+            (not_found(), Status::NotFound),
+            (error_responce(Status::MethodNotAllowed), Status::MethodNotAllowed),
+            (conflict("floor with this number already exists"), Status::Conflict),
+            (error_responce(Status::PreconditionFailed), Status::PreconditionFailed),
+            (http::Error::ContentTooLarge, Status::ContentTooLarge),
+            (invalid("wrong".into()), Status::UnprocessableContent),
+            (http::Error::InternalServerError("broken".into()), Status::InternalServerError),
+        ];
+
+        for (e, expected) in cases {
+            assert_eq!(error(e).0, expected);
+        }
+
+        // Внутренние подробности не попадают в ответ
+        let (_, json) = error(http::Error::InternalServerError("secret".into()));
+        assert!(json.get("detail").is_none());
+    }
+}
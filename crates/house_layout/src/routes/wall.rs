@@ -0,0 +1,172 @@
+use gauge::house_layout::{house::House, wall::{Door, Wall}};
+use http::{router::Params, HttpRequest, HttpResponce, Status};
+use json_minimal::Json;
+
+use crate::App;
+
+use super::{app_read, array_responce, created, house::{room, room_mut}, invalid, json_content, json_responce, no_content, not_found};
+
+fn wall<'a>(house: &'a House, params: &Params) -> crate::Result<&'a Wall> {
+    room(house, params)?
+        .walls
+        .get(params.get::<usize>("wall")?)
+        .ok_or_else(not_found)
+}
+
+fn wall_mut<'a>(house: &'a mut House, params: &Params) -> crate::Result<&'a mut Wall> {
+    let wall: usize = params.get("wall")?;
+
+    room_mut(house, params)?
+        .walls
+        .get_mut(wall)
+        .ok_or_else(not_found)
+}
+
+fn door_mut<'a>(house: &'a mut House, params: &Params) -> crate::Result<&'a mut Door> {
+    let door: usize = params.get("door")?;
+
+    wall_mut(house, params)?
+        .door_mut(door)
+        .ok_or_else(not_found)
+}
+
+// WALL
+
+pub(super) fn get_walls(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let walls: Vec<Json> = room(&app.house, &params)?
+        .walls
+        .iter()
+        .map(|w| w.clone().into())
+        .collect()
+    ;
+
+    Ok(array_responce(walls))
+}
+
+pub(super) fn get_wall(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let wall = wall(&app.house, &params)?;
+
+    Ok(json_responce(Status::OK, wall.clone().into()))
+}
+
+pub(super) fn add_wall(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let wall = Wall::try_from(&json_content(&request)?)
+        .map_err(invalid)?;
+    wall.validate().map_err(invalid)?;
+
+    let room = room_mut(house, &params)?;
+
+    let mut patched = room.clone();
+    patched.walls.push(wall.clone());
+    patched.validate().map_err(invalid)?;
+    *room = patched;
+
+    Ok(created(&request, room.walls.len() - 1, wall.into()))
+}
+
+pub(super) fn patch_wall(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let content = json_content(&request)?;
+    let idx: usize = params.get("wall")?;
+
+    let room = room_mut(house, &params)?;
+
+    let mut patched = room.clone();
+    let wall = patched.walls
+        .get_mut(idx)
+        .ok_or_else(not_found)?;
+    wall.patch(&content).map_err(invalid)?;
+    wall.validate().map_err(invalid)?;
+    let wall = wall.clone();
+
+    patched.validate().map_err(invalid)?;
+    *room = patched;
+
+    Ok(json_responce(Status::OK, wall.into()))
+}
+
+pub(super) fn delete_wall(_request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let wall: usize = params.get("wall")?;
+
+    let room = room_mut(house, &params)?;
+    if wall >= room.walls.len() {
+        return Err(not_found());
+    }
+
+    let mut patched = room.clone();
+    patched.walls.remove(wall);
+    patched.validate().map_err(invalid)?;
+    *room = patched;
+
+    Ok(no_content())
+}
+
+// DOOR
+
+pub(super) fn get_doors(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let doors: Vec<Json> = wall(&app.house, &params)?
+        .doors()
+        .iter()
+        .map(|d| d.clone().into())
+        .collect()
+    ;
+
+    Ok(array_responce(doors))
+}
+
+pub(super) fn get_door(_request: HttpRequest, params: Params, app: &App) -> crate::Result<HttpResponce> {
+    let app = app_read(app)?;
+    let door = wall(&app.house, &params)?
+        .doors()
+        .get(params.get::<usize>("door")?)
+        .ok_or_else(not_found)?
+    ;
+
+    Ok(json_responce(Status::OK, door.clone().into()))
+}
+
+pub(super) fn add_door(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let door = Door::try_from(&json_content(&request)?)
+        .map_err(invalid)?;
+
+    let wall = wall_mut(house, &params)?;
+    wall.check_door(&door)
+        .map_err(|e| invalid(e.into()))?;
+
+    let doors = wall.doors_mut();
+    doors.push(door.clone());
+
+    Ok(created(&request, doors.len() - 1, door.into()))
+}
+
+pub(super) fn patch_door(request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let content = json_content(&request)?;
+
+    let wall = wall(house, &params)?;
+    let mut patched = wall
+        .doors()
+        .get(params.get::<usize>("door")?)
+        .ok_or_else(not_found)?
+        .clone()
+    ;
+    patched.patch(&content).map_err(invalid)?;
+    wall.check_door(&patched)
+        .map_err(|e| invalid(e.into()))?;
+
+    *door_mut(house, &params)? = patched.clone();
+
+    Ok(json_responce(Status::OK, patched.into()))
+}
+
+pub(super) fn delete_door(_request: HttpRequest, params: Params, house: &mut House) -> crate::Result<HttpResponce> {
+    let door: usize = params.get("door")?;
+
+    wall_mut(house, &params)?
+        .remove_door(door)
+        .ok_or_else(not_found)?
+    ;
+
+    Ok(no_content())
+}
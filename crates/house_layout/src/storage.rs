@@ -0,0 +1,294 @@
+//! Хранение дома на диске в виде JSON
+//!
+//! Каждое изменение записывается во временный файл, который затем
+//! атомарно переименовывается в [`SNAPSHOT`]. Перед этим предыдущий снимок
+//! становится [`BACKUP`] через жесткую ссылку, так что [`SNAPSHOT`] есть
+//! на диске в любой момент, а при поврежденном основном файле
+//! можно загрузить последний рабочий.
+//!
+//! Снимок хранит ревизию журнала, из которой он сохранен.
+//!
+//! Журнал изменений (см. [`crate::journal`]) дописывается
+//! в [`JOURNAL`] по одной записи на строку. Когда в файле накапливается
+//! вдвое больше записей, чем хранит журнал, файл переписывается заново.
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use gauge::house_layout::house::House;
+use json_minimal::Json;
+
+use crate::journal::Entry;
+
+const SNAPSHOT: &str = "house.json";
+const BACKUP: &str = "house.json.bak";
+const TEMPORARY: &str = "house.json.tmp";
+
+/// Дом из снимка и ревизия журнала, если она записана в снимке
+pub(crate) type Snapshot = (House, Option<u64>);
+const JOURNAL: &str = "journal.jsonl";
+
+pub(crate) struct Storage {
+    dir: PathBuf,
+    /// Сколько записей сейчас в [`JOURNAL`]
+    journal_len: usize,
+}
+
+impl Storage {
+    /// Создает директорию `dir`, если ее еще нет
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir, journal_len: 0 })
+    }
+
+    /// Загружает последний рабочий снимок дома и его ревизию, если она записана.
+    /// Возвращает `Ok(None)`, если снимков еще нет, и ошибку, если снимки
+    /// есть, но ни один не удалось прочитать: пустой дом затер бы их при сохранении
+    pub(crate) fn load(&self) -> Result<Option<Snapshot>, Box<dyn Error>> {
+        let mut error = None;
+
+        for file in [SNAPSHOT, BACKUP] {
+            let path = self.dir.join(file);
+
+            match read_house(&path) {
+                Ok(Some(house)) => return Ok(Some(house)),
+                Ok(None) => (),
+                Err(e) => {
+                    eprintln!("Error loading {}: {e}", path.display());
+                    error = Some(format!("{} is corrupted: {e}", path.display()));
+                },
+            }
+        }
+
+        match error {
+            Some(e) => Err(e)?,
+            None => Ok(None),
+        }
+    }
+
+    /// Атомарно записывает снимок дома ревизии `revision`
+    pub(crate) fn save(&self, house: &House, revision: u64) -> io::Result<()> {
+        let json = Json::JSON(vec![
+            Json::OBJECT {
+                name: "revision".to_string(),
+                value: Box::new(Json::NUMBER(revision as f64))
+            },
+            house.clone().into(),
+        ]);
+        let temporary = self.dir.join(TEMPORARY);
+        let snapshot = self.dir.join(SNAPSHOT);
+
+        let mut file = File::create(&temporary)?;
+        file.write_all(json.print().as_bytes())?;
+        file.sync_all()?;
+
+        if snapshot.exists() {
+            let backup = self.dir.join(BACKUP);
+            match fs::remove_file(&backup) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => (),
+            }
+            fs::hard_link(&snapshot, &backup)?;
+        }
+        fs::rename(&temporary, &snapshot)?;
+
+        // Переименование попадет на диск только вместе с директорией
+        File::open(&self.dir)?.sync_all()
+    }
+
+    /// Дописывает запись в конец журнала
+    pub(crate) fn append(&mut self, entry: &Entry) -> io::Result<()> {
+        let json: Json = entry.clone().into();
+
+        let mut file = File::options()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(JOURNAL))?;
+
+        writeln!(file, "{}", json.print())?;
+        file.sync_data()?;
+
+        self.journal_len += 1;
+
+        Ok(())
+    }
+
+    pub(crate) fn journal_len(&self) -> usize {
+        self.journal_len
+    }
+
+    /// Переписывает журнал, оставляя только `entries`
+    pub(crate) fn compact(&mut self, entries: &[Entry]) -> io::Result<()> {
+        self.rewrite_journal(entries)?;
+        self.journal_len = entries.len();
+
+        Ok(())
+    }
+
+    /// Читает журнал до первой поврежденной записи,
+    /// например недописанной из-за падения процесса.
+    /// Поврежденный хвост отбрасывается, чтобы новые записи не оказались за ним.
+    /// Запись, после которой не удалось сохранить снимок, заменяется
+    /// следующей записью с той же ревизией
+    pub(crate) fn load_journal(&mut self) -> Vec<Entry> {
+        let path = self.dir.join(JOURNAL);
+
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Error loading {}: {e}", path.display());
+                }
+                return vec![];
+            },
+        };
+
+        let mut entries: Vec<Entry> = vec![];
+        let mut corrupted = false;
+        let mut lines = 0;
+
+        for line in BufReader::new(file).lines() {
+            let entry = line
+                .map_err(Box::<dyn Error>::from)
+                .and_then(|line| {
+                    let json = Json::parse(line.as_bytes())
+                        .map_err(|(idx, msg)| format!("{msg} at {idx}"))?;
+                    Entry::try_from(&json)
+                });
+
+            lines += 1;
+
+            // После сжатия журнал начинается не с нулевой ревизии
+            let first = entries.first().map_or(0, |e| e.revision);
+            let next = entries.last().map_or(u64::MAX, |e| e.revision + 1);
+
+            match entry {
+                Ok(entry) if (first..=next).contains(&entry.revision) => {
+                    entries.truncate((entry.revision - first) as usize);
+                    entries.push(entry);
+                },
+                Ok(entry) => {
+                    eprintln!("Error loading {}: unexpected revision {}", path.display(), entry.revision);
+                    corrupted = true;
+                    break;
+                },
+                Err(e) => {
+                    eprintln!("Error loading {}: {e}", path.display());
+                    corrupted = true;
+                    break;
+                },
+            }
+        }
+
+        self.journal_len = lines;
+
+        if corrupted {
+            if let Err(e) = self.compact(&entries) {
+                eprintln!("Error truncating {}: {e}", path.display());
+            }
+        }
+
+        entries
+    }
+
+    fn rewrite_journal(&self, entries: &[Entry]) -> io::Result<()> {
+        let temporary = self.dir.join(JOURNAL).with_extension("jsonl.tmp");
+
+        let mut file = File::create(&temporary)?;
+        for entry in entries {
+            let json: Json = entry.clone().into();
+            writeln!(file, "{}", json.print())?;
+        }
+        file.sync_all()?;
+
+        fs::rename(&temporary, self.dir.join(JOURNAL))
+    }
+}
+
+/// `Ok(None)`, если файла нет
+fn read_house(path: &Path) -> Result<Option<Snapshot>, Box<dyn Error>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => Err(e)?,
+    };
+
+    let json = Json::parse(&bytes)
+        .map_err(|(idx, msg)| format!("{msg} at {idx}"))?;
+
+    let revision = match json.get("revision").map(|v| v.unbox()) {
+        Some(Json::NUMBER(revision)) => Some(*revision as u64),
+        _ => None,
+    };
+
+    Ok(Some((House::try_from(&json)?, revision)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use gauge::house_layout::house::House;
+
+    use crate::journal::Journal;
+
+    use super::{Storage, BACKUP, SNAPSHOT};
+
+    #[test]
+    fn save_keeps_backup_and_refuses_corrupted() {
+        let dir = std::env::temp_dir().join(format!("house_layout_storage_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = Storage::new(&dir).unwrap();
+
+        assert!(storage.load().unwrap().is_none());
+
+        let mut house = House::default();
+        storage.save(&house, 0).unwrap();
+        house.push_floor();
+        storage.save(&house, 1).unwrap();
+
+        let (loaded, revision) = storage.load().unwrap().unwrap();
+        assert_eq!((loaded.floors().len(), revision), (2, Some(1)));
+
+        // Основной снимок поврежден, загружается предыдущий
+        fs::write(dir.join(SNAPSHOT), "{").unwrap();
+        assert_eq!(storage.load().unwrap().unwrap().0.floors().len(), 1);
+
+        fs::write(dir.join(BACKUP), "{").unwrap();
+        assert!(storage.load().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn journal_compaction() {
+        let dir = std::env::temp_dir().join(format!("house_layout_journal_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut storage = Storage::new(&dir).unwrap();
+        let mut journal = Journal::default();
+
+        for _ in 0..5 {
+            let entry = journal.prepare("add floor", "/house/floor", House::default());
+            storage.append(&entry).unwrap();
+            journal.record(entry);
+        }
+
+        storage.compact(&journal.entries()[3..]).unwrap();
+        assert_eq!(storage.journal_len(), 2);
+
+        // Запись, после которой снимок не сохранился, заменяется следующей
+        let entry = journal.prepare("add room", "/house/floor/0/room", House::default());
+        storage.append(&entry).unwrap();
+        storage.append(&entry).unwrap();
+
+        let mut reopened = Storage::new(&dir).unwrap();
+        let revisions: Vec<u64> = reopened.load_journal().iter().map(|e| e.revision).collect();
+        assert_eq!(revisions, [3, 4, 5]);
+        assert_eq!(reopened.journal_len(), 4);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
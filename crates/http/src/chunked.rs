@@ -0,0 +1,99 @@
+//! Кодирование `Transfer-Encoding: chunked`
+use std::io::{BufRead, Read};
+
+use crate::{Error, Headers, Result, SEPARATOR};
+
+/// Завершающий блок с нулевой длиной и пустым списком трейлеров
+pub(crate) const LAST_CHUNK: &str = "0\r\n\r\n";
+
+/// Кодирует один блок. Пустой блок кодировать нельзя,
+/// так как он означает конец содержания
+pub(crate) fn encode_chunk(chunk: &str) -> String {
+    format!("{:X}{SEPARATOR}{chunk}{SEPARATOR}", chunk.len())
+}
+
+/// Читает содержание, закодированное блоками, и трейлеры после него.
+///
+/// Содержание больше `content_limit` байт отклоняется с [`Error::ContentTooLarge`]
+pub(crate) fn decode(reader: &mut impl BufRead, content_limit: usize) -> Result<(String, Headers)> {
+    let mut content: Vec<u8> = vec![];
+
+    loop {
+        let size_line = read_line(reader)?;
+        let size = size_line
+            .split(';')
+            .next()
+            .map(|s| s.trim())
+            .ok_or(Error::MalformedRequest)?
+        ;
+        // `from_str_radix` пропускает знак, например `+a`
+        if !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(Error::MalformedRequest);
+        }
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| Error::MalformedRequest)?
+        ;
+
+        if size == 0 {
+            break;
+        }
+
+        if size > content_limit.saturating_sub(content.len()) {
+            return Err(Error::ContentTooLarge);
+        }
+
+        // Размер блока пришел от собеседника, поэтому память под него
+        // заранее не выделяется: короткий блок закончится раньше
+        let read = reader
+            .take(size as u64)
+            .read_to_end(&mut content)
+            .map_err(|_| Error::MalformedRequest)?
+        ;
+        if read != size {
+            return Err(Error::MalformedRequest);
+        }
+
+        read_line(reader)?
+            .is_empty()
+            .then_some(())
+            .ok_or(Error::MalformedRequest)?
+        ;
+    }
+
+    let mut trailers = Headers::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+
+        let (k,v) = Headers::parse_line(&line)?;
+        trailers.append(k, v);
+    }
+
+    let content = String::from_utf8(content)
+        .map_err(|_| Error::MalformedRequest)?
+    ;
+
+    Ok((content, trailers))
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+
+    let read = reader
+        .read_line(&mut line)
+        .map_err(|_| Error::MalformedRequest)?
+    ;
+    if read == 0 {
+        return Err(Error::MalformedRequest);
+    }
+
+    let line = line
+        .strip_suffix('\n')
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .unwrap_or(&line)
+    ;
+
+    Ok(line.to_string())
+}
@@ -0,0 +1,169 @@
+//! Блокирующий HTTP клиент
+use std::{io::{BufReader, Write}, net::{TcpStream, ToSocketAddrs}, time::Duration};
+
+use crate::{uri, Error, HttpRequest, HttpResponce, Method, Result, Status, DEFAULT_CONTENT_LIMIT};
+
+const DEFAULT_PORT: u16 = 80;
+
+/// Отправляет запросы по адресу из заголовка `Host`,
+/// каждый запрос через новое соединение
+#[derive(Debug, Clone)]
+pub struct Client {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    max_redirects: usize,
+    content_limit: usize
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(30),
+            max_redirects: 5,
+            content_limit: DEFAULT_CONTENT_LIMIT
+        }
+    }
+}
+
+impl Client {
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Сколько перенаправлений пройти, прежде чем вернуть [`Error::TooManyRedirects`].
+    /// При `0` перенаправления возвращаются как есть
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn content_limit(mut self, content_limit: usize) -> Self {
+        self.content_limit = content_limit;
+        self
+    }
+
+    /// Отправляет запрос на адрес из заголовка `Host` и читает ответ,
+    /// следуя перенаправлениям 301, 302, 303, 307 и 308
+    pub fn send(&self, request: HttpRequest) -> Result<HttpResponce> {
+        let mut request = request;
+        let mut redirects = 0;
+
+        loop {
+            let responce = self.send_once(request.clone())?;
+
+            let location = match responce.status() {
+                Status::MovedPermanently
+                | Status::Found
+                | Status::SeeOther
+                | Status::TemporaryRedirect
+                | Status::PermanentRedirect if self.max_redirects > 0 =>
+                    responce.headers().get("Location"),
+                _ => None,
+            };
+
+            let Some(location) = location else {
+                return Ok(responce);
+            };
+
+            if redirects == self.max_redirects {
+                return Err(Error::TooManyRedirects);
+            }
+            redirects += 1;
+
+            request = redirect(request, responce.status(), location)?;
+        }
+    }
+
+    fn send_once(&self, mut request: HttpRequest) -> Result<HttpResponce> {
+        let host = request.headers()
+            .get("Host")
+            .ok_or(Error::MalformedRequest)?
+            .to_string()
+        ;
+
+        let mut stream = self.connect(&host)?;
+        stream
+            .set_read_timeout(Some(self.read_timeout))
+            .map_err(Error::ConnectionError)?
+        ;
+
+        // Соединение не переиспользуется, поэтому конец содержания без длины - это его закрытие
+        request.headers.insert("Connection".to_string(), "close".to_string());
+
+        let method = *request.method();
+        stream
+            .write_all(request.serialize().as_bytes())
+            .map_err(Error::ConnectionError)?
+        ;
+
+        let mut reader = BufReader::new(stream);
+
+        HttpResponce::read(&mut reader, method, self.content_limit)
+    }
+
+    fn connect(&self, host: &str) -> Result<TcpStream> {
+        let address = match host.rsplit_once(':') {
+            Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+            _ => format!("{host}:{DEFAULT_PORT}"),
+        };
+
+        let mut last_error = None;
+        for address in address.to_socket_addrs().map_err(Error::ConnectionError)? {
+            match TcpStream::connect_timeout(&address, self.connect_timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(Error::ConnectionError(
+            last_error.unwrap_or_else(|| std::io::ErrorKind::NotFound.into())
+        ))
+    }
+}
+
+/// Запрос по адресу из `Location`. После 303, а также 301 и 302 на POST,
+/// запрос повторяется методом GET без содержания
+fn redirect(mut request: HttpRequest, status: &Status, location: &str) -> Result<HttpRequest> {
+    let target = match location.strip_prefix("http://") {
+        Some(rest) => {
+            let (host, target) = match rest.find('/') {
+                Some(idx) => rest.split_at(idx),
+                None => (rest, "/"),
+            };
+            request.headers.insert("Host".to_string(), host.to_string());
+
+            target.to_string()
+        },
+        None if location.starts_with('/') => location.to_string(),
+        // Другие схемы и относительные пути не поддерживаются
+        None => return Err(Error::MalformedResponce),
+    };
+
+    let to_get = match status {
+        Status::SeeOther => request.method != Method::HEAD,
+        Status::MovedPermanently | Status::Found => request.method == Method::POST,
+        _ => false,
+    };
+
+    if to_get {
+        request.method = Method::GET;
+        request.content = None;
+        request.headers.remove("Content-Length");
+        request.headers.remove("Transfer-Encoding");
+        request.headers.remove("Content-Type");
+    }
+
+    let (path, query) = uri::split_target(&target)?;
+    request.target = target;
+    request.path = path;
+    request.query = query;
+
+    Ok(request)
+}
@@ -0,0 +1,169 @@
+//! Заголовки HTTP сообщения
+use crate::{Error, Result, SEPARATOR};
+
+/// Заголовки в порядке добавления.
+///
+/// Имена сравниваются без учета регистра, одно имя может встречаться
+/// несколько раз (например `Set-Cookie`)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Headers(Vec<(String,String)>);
+
+impl Headers {
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+
+    /// Заменяет все значения заголовка `key` одним значением.
+    /// Заголовок остается на месте первого вхождения
+    pub fn insert(&mut self, key: String, value: String) {
+        let mut value = Some(value);
+
+        self.0.retain_mut(|(k,v)| {
+            if !k.eq_ignore_ascii_case(&key) {
+                return true;
+            }
+
+            match value.take() {
+                Some(value) => {
+                    *v = value;
+                    true
+                },
+                None => false,
+            }
+        });
+
+        if let Some(value) = value {
+            self.0.push((key, value))
+        }
+    }
+
+    /// Добавляет еще одно значение заголовка, не трогая уже имеющиеся
+    pub fn append(&mut self, key: String, value: String) {
+        self.0.push((key, value))
+    }
+
+    /// Первое значение заголовка
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k,_)| k.eq_ignore_ascii_case(key))
+            .map(|(_,v)| v.as_str())
+    }
+
+    /// Все значения заголовка в порядке добавления
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(k,_)| k.eq_ignore_ascii_case(key))
+            .map(|(_,v)| v.as_str())
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.0.retain(|(k,_)| !k.eq_ignore_ascii_case(key))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(k,v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.get("Content-Type")
+    }
+
+    pub fn set_content_type(&mut self, content_type: &str) {
+        self.insert("Content-Type".to_string(), content_type.to_string())
+    }
+
+    /// Значение `Content-Length`. Если оно не является числом,
+    /// возвращает [`Error::MalformedHeader`]
+    pub fn content_length(&self) -> Result<Option<usize>> {
+        self.get("Content-Length")
+            .map(|length|
+                length
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::MalformedHeader)
+            )
+            .transpose()
+    }
+
+    pub fn set_content_length(&mut self, length: usize) {
+        self.insert("Content-Length".to_string(), length.to_string())
+    }
+
+    /// Параметры `Connection` в нижнем регистре
+    pub fn connection(&self) -> Vec<String> {
+        self.list("Connection")
+    }
+
+    /// Содержание закодировано блоками (`Transfer-Encoding: chunked`)
+    pub fn is_chunked(&self) -> bool {
+        self.list("Transfer-Encoding")
+            .iter()
+            .any(|e| e == "chunked")
+    }
+
+    /// Значения заголовка, перечисленные через запятую, в нижнем регистре
+    fn list(&self, key: &str) -> Vec<String> {
+        self.get_all(key)
+            .flat_map(|v| v.split(','))
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+
+    /// Разбирает строку заголовка вида `Имя: значение`
+    pub(crate) fn parse_line(line: &str) -> Result<(String,String)> {
+        let (k,v) = line
+            .split_once(':')
+            .ok_or(Error::MalformedHeader)?
+        ;
+
+        if k.is_empty() || k.contains(char::is_whitespace) {
+            return Err(Error::MalformedHeader);
+        }
+
+        Ok((k.to_string(), v.trim().to_string()))
+    }
+
+    /// Сериализует заголовки, каждый с новой строки
+    pub(crate) fn serialize(&self) -> String {
+        self.0
+            .iter()
+            .map(|(k,v)| [SEPARATOR,[k.as_str(),v.as_str()].join(": ").as_str()].join(""))
+            .collect::<Vec<String>>()
+            .join("")
+    }
+}
+
+impl Extend<(String,String)> for Headers {
+    /// Добавляет заголовки через [`Headers::append`]
+    fn extend<T: IntoIterator<Item = (String,String)>>(&mut self, iter: T) {
+        for (k,v) in iter {
+            self.append(k, v)
+        }
+    }
+}
+
+impl IntoIterator for Headers {
+    type Item = (String,String);
+    type IntoIter = std::vec::IntoIter<(String,String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
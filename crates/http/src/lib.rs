@@ -1,65 +1,307 @@
 // TODO: no .clone()
-use std::{collections::HashMap, fmt::Display, io::{BufRead, BufReader, Lines}, net::TcpStream, ops::Not};
+use std::{fmt::{Debug, Display}, io::{BufRead, BufReader, Read, Write}, net::TcpStream};
+
+mod chunked;
+pub mod client;
+mod headers;
+pub mod pool;
+pub mod router;
+pub mod shutdown;
+mod uri;
+
+pub use headers::Headers;
+pub use uri::Query;
 
 pub type Result<T> = std::result::Result<T,crate::Error>;
 
 const HTTP_VERSION: &str = "HTTP/1.1";
 const SEPARATOR: &str = "\r\n";
 
-#[derive(Debug,Clone,Copy)]
+/// Максимальный размер тела запроса по умолчанию, в байтах
+pub const DEFAULT_CONTENT_LIMIT: usize = 1024 * 1024;
+
+/// Коды состояния из реестра RFC 9110 (и RFC 6585).
+/// Остальные коды представлены [`Status::Custom`] вместе с фразой из строки состояния
+#[derive(Debug,Clone,PartialEq,Eq)]
 pub enum Status {
+    Continue,
+    SwitchingProtocols,
     OK,
     Created,
-    InternalServerError,
+    Accepted,
+    NonAuthoritativeInformation,
+    NoContent,
+    ResetContent,
+    PartialContent,
+    MultipleChoices,
+    MovedPermanently,
+    Found,
+    SeeOther,
+    NotModified,
+    UseProxy,
+    TemporaryRedirect,
+    PermanentRedirect,
     BadRequest,
+    Unauthorized,
+    PaymentRequired,
+    Forbidden,
     NotFound,
-    MethodNotAllowed
+    MethodNotAllowed,
+    NotAcceptable,
+    ProxyAuthenticationRequired,
+    RequestTimeout,
+    Conflict,
+    Gone,
+    LengthRequired,
+    PreconditionFailed,
+    ContentTooLarge,
+    URITooLong,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    ExpectationFailed,
+    MisdirectedRequest,
+    UnprocessableContent,
+    UpgradeRequired,
+    PreconditionRequired,
+    TooManyRequests,
+    RequestHeaderFieldsTooLarge,
+    InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    HTTPVersionNotSupported,
+    NetworkAuthenticationRequired,
+    Custom(u16, String)
+}
+
+impl Status {
+    /// Известный статус по коду, иначе [`Status::Custom`] с фразой `reason`
+    pub fn from_code(code: u16, reason: &str) -> Self {
+        match code {
+            100 => Status::Continue,
+            101 => Status::SwitchingProtocols,
+            200 => Status::OK,
+            201 => Status::Created,
+            202 => Status::Accepted,
+            203 => Status::NonAuthoritativeInformation,
+            204 => Status::NoContent,
+            205 => Status::ResetContent,
+            206 => Status::PartialContent,
+            300 => Status::MultipleChoices,
+            301 => Status::MovedPermanently,
+            302 => Status::Found,
+            303 => Status::SeeOther,
+            304 => Status::NotModified,
+            305 => Status::UseProxy,
+            307 => Status::TemporaryRedirect,
+            308 => Status::PermanentRedirect,
+            400 => Status::BadRequest,
+            401 => Status::Unauthorized,
+            402 => Status::PaymentRequired,
+            403 => Status::Forbidden,
+            404 => Status::NotFound,
+            405 => Status::MethodNotAllowed,
+            406 => Status::NotAcceptable,
+            407 => Status::ProxyAuthenticationRequired,
+            408 => Status::RequestTimeout,
+            409 => Status::Conflict,
+            410 => Status::Gone,
+            411 => Status::LengthRequired,
+            412 => Status::PreconditionFailed,
+            413 => Status::ContentTooLarge,
+            414 => Status::URITooLong,
+            415 => Status::UnsupportedMediaType,
+            416 => Status::RangeNotSatisfiable,
+            417 => Status::ExpectationFailed,
+            421 => Status::MisdirectedRequest,
+            422 => Status::UnprocessableContent,
+            426 => Status::UpgradeRequired,
+            428 => Status::PreconditionRequired,
+            429 => Status::TooManyRequests,
+            431 => Status::RequestHeaderFieldsTooLarge,
+            500 => Status::InternalServerError,
+            501 => Status::NotImplemented,
+            502 => Status::BadGateway,
+            503 => Status::ServiceUnavailable,
+            504 => Status::GatewayTimeout,
+            505 => Status::HTTPVersionNotSupported,
+            511 => Status::NetworkAuthenticationRequired,
+            _ => Status::Custom(code, reason.to_string()),
+        }
+    }
+
+    pub const fn code(&self) -> u16 {
+        match self {
+            Status::Continue                      => 100,
+            Status::SwitchingProtocols            => 101,
+            Status::OK                            => 200,
+            Status::Created                       => 201,
+            Status::Accepted                      => 202,
+            Status::NonAuthoritativeInformation   => 203,
+            Status::NoContent                     => 204,
+            Status::ResetContent                  => 205,
+            Status::PartialContent                => 206,
+            Status::MultipleChoices               => 300,
+            Status::MovedPermanently              => 301,
+            Status::Found                         => 302,
+            Status::SeeOther                      => 303,
+            Status::NotModified                   => 304,
+            Status::UseProxy                      => 305,
+            Status::TemporaryRedirect             => 307,
+            Status::PermanentRedirect             => 308,
+            Status::BadRequest                    => 400,
+            Status::Unauthorized                  => 401,
+            Status::PaymentRequired               => 402,
+            Status::Forbidden                     => 403,
+            Status::NotFound                      => 404,
+            Status::MethodNotAllowed              => 405,
+            Status::NotAcceptable                 => 406,
+            Status::ProxyAuthenticationRequired   => 407,
+            Status::RequestTimeout                => 408,
+            Status::Conflict                      => 409,
+            Status::Gone                          => 410,
+            Status::LengthRequired                => 411,
+            Status::PreconditionFailed            => 412,
+            Status::ContentTooLarge               => 413,
+            Status::URITooLong                    => 414,
+            Status::UnsupportedMediaType          => 415,
+            Status::RangeNotSatisfiable           => 416,
+            Status::ExpectationFailed             => 417,
+            Status::MisdirectedRequest            => 421,
+            Status::UnprocessableContent          => 422,
+            Status::UpgradeRequired               => 426,
+            Status::PreconditionRequired          => 428,
+            Status::TooManyRequests               => 429,
+            Status::RequestHeaderFieldsTooLarge   => 431,
+            Status::InternalServerError           => 500,
+            Status::NotImplemented                => 501,
+            Status::BadGateway                    => 502,
+            Status::ServiceUnavailable            => 503,
+            Status::GatewayTimeout                => 504,
+            Status::HTTPVersionNotSupported       => 505,
+            Status::NetworkAuthenticationRequired => 511,
+            Status::Custom(code, _) => *code,
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            Status::Continue                      => "Continue",
+            Status::SwitchingProtocols            => "Switching Protocols",
+            Status::OK                            => "OK",
+            Status::Created                       => "Created",
+            Status::Accepted                      => "Accepted",
+            Status::NonAuthoritativeInformation   => "Non-Authoritative Information",
+            Status::NoContent                     => "No Content",
+            Status::ResetContent                  => "Reset Content",
+            Status::PartialContent                => "Partial Content",
+            Status::MultipleChoices               => "Multiple Choices",
+            Status::MovedPermanently              => "Moved Permanently",
+            Status::Found                         => "Found",
+            Status::SeeOther                      => "See Other",
+            Status::NotModified                   => "Not Modified",
+            Status::UseProxy                      => "Use Proxy",
+            Status::TemporaryRedirect             => "Temporary Redirect",
+            Status::PermanentRedirect             => "Permanent Redirect",
+            Status::BadRequest                    => "Bad Request",
+            Status::Unauthorized                  => "Unauthorized",
+            Status::PaymentRequired               => "Payment Required",
+            Status::Forbidden                     => "Forbidden",
+            Status::NotFound                      => "Not Found",
+            Status::MethodNotAllowed              => "Method Not Allowed",
+            Status::NotAcceptable                 => "Not Acceptable",
+            Status::ProxyAuthenticationRequired   => "Proxy Authentication Required",
+            Status::RequestTimeout                => "Request Timeout",
+            Status::Conflict                      => "Conflict",
+            Status::Gone                          => "Gone",
+            Status::LengthRequired                => "Length Required",
+            Status::PreconditionFailed            => "Precondition Failed",
+            Status::ContentTooLarge               => "Content Too Large",
+            Status::URITooLong                    => "URI Too Long",
+            Status::UnsupportedMediaType          => "Unsupported Media Type",
+            Status::RangeNotSatisfiable           => "Range Not Satisfiable",
+            Status::ExpectationFailed             => "Expectation Failed",
+            Status::MisdirectedRequest            => "Misdirected Request",
+            Status::UnprocessableContent          => "Unprocessable Content",
+            Status::UpgradeRequired               => "Upgrade Required",
+            Status::PreconditionRequired          => "Precondition Required",
+            Status::TooManyRequests               => "Too Many Requests",
+            Status::RequestHeaderFieldsTooLarge   => "Request Header Fields Too Large",
+            Status::InternalServerError           => "Internal Server Error",
+            Status::NotImplemented                => "Not Implemented",
+            Status::BadGateway                    => "Bad Gateway",
+            Status::ServiceUnavailable            => "Service Unavailable",
+            Status::GatewayTimeout                => "Gateway Timeout",
+            Status::HTTPVersionNotSupported       => "HTTP Version Not Supported",
+            Status::NetworkAuthenticationRequired => "Network Authentication Required",
+            Status::Custom(_, reason) => reason,
+        }
+    }
+
+    /// 1xx
+    pub const fn is_informational(&self) -> bool { matches!(self.code(), 100..=199) }
+    /// 2xx
+    pub const fn is_success(&self) -> bool { matches!(self.code(), 200..=299) }
+    /// 3xx
+    pub const fn is_redirection(&self) -> bool { matches!(self.code(), 300..=399) }
+    /// 4xx
+    pub const fn is_client_error(&self) -> bool { matches!(self.code(), 400..=499) }
+    /// 5xx
+    pub const fn is_server_error(&self) -> bool { matches!(self.code(), 500..=599) }
 }
 
 impl TryFrom<&str> for Status {
     type Error = Error;
 
+    /// Код из трех цифр. Фраза для неизвестных кодов остается пустой
     fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        match value {
-            "200" => Ok(Status::OK),
-            "201" => Ok(Status::Created),
-            "500" => Ok(Status::InternalServerError),
-            "400" => Ok(Status::BadRequest),
-            "404" => Ok(Status::NotFound),
-            "405" => Ok(Status::MethodNotAllowed),
-            _ => Err(Error::UnknownStatus)
+        if value.len() != 3 {
+            return Err(Error::UnknownStatus);
         }
+
+        let code: u16 = value
+            .parse()
+            .map_err(|_| Error::UnknownStatus)?
+        ;
+
+        if !(100..=999).contains(&code) {
+            return Err(Error::UnknownStatus);
+        }
+
+        Ok(Status::from_code(code, ""))
     }
 }
 
 impl Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let message = match self {
-            Status::OK => "200 OK",
-            Status::Created => "201 Created",
-            Status::InternalServerError => "500 Internal Server Error",
-            Status::BadRequest => "400 Bad Request",
-            Status::NotFound => "404 Not Found",
-            Status::MethodNotAllowed => "405 Method Not Allowed",
-        };
-
-        write!(f,"{message}")
+        write!(f,"{} {}",self.code(),self.reason())
     }
 }
 
-pub type Headers = HashMap<String,String>;
 pub type Content = Option<String>;
 
+/// Содержание ответа, которое формируется по частям
+/// и отправляется с `Transfer-Encoding: chunked`
+pub struct ContentStream(Box<dyn Iterator<Item = String> + Send>);
+
+impl Debug for ContentStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"ContentStream")
+    }
+}
+
 #[derive(Debug)]
 pub struct HttpResponceBuilder {
     status: Status,
     headers: Headers,
-    content: Content
+    content: Content,
+    stream: Option<ContentStream>
 }
 
 impl Default for HttpResponceBuilder {
     fn default() -> Self {
-        Self { status: Status::OK, headers: HashMap::new(), content: None }
+        Self { status: Status::OK, headers: Headers::new(), content: None, stream: None }
     }
 }
 
@@ -69,6 +311,12 @@ impl HttpResponceBuilder {
         self
     }
 
+    /// Добавляет значение заголовка, не заменяя предыдущие
+    pub fn append_header(mut self, key: String, value: String) -> Self {
+        self.headers.append(key, value);
+        self
+    }
+
     pub fn status(mut self, status: Status) -> Self {
         self.status = status;
         self
@@ -76,13 +324,25 @@ impl HttpResponceBuilder {
 
     pub fn content(mut self, content: &dyn ToString) -> Self {
         let content = content.to_string();
-        self.headers.insert("Content-Length".to_string(), content.len().to_string());
+        self.headers.remove("Transfer-Encoding");
+        self.headers.set_content_length(content.len());
         self.content = Some(content);
+        self.stream = None;
+        self
+    }
+
+    /// Содержание, длина которого заранее неизвестна.
+    /// Каждый элемент `chunks` отправляется отдельным блоком по мере готовности
+    pub fn chunked(mut self, chunks: impl Iterator<Item = String> + Send + 'static) -> Self {
+        self.headers.remove("Content-Length");
+        self.headers.insert("Transfer-Encoding".to_string(), "chunked".to_string());
+        self.content = None;
+        self.stream = Some(ContentStream(Box::new(chunks)));
         self
     }
 
     pub fn build(self) -> HttpResponce {
-        HttpResponce { status: self.status, headers: self.headers, content: self.content }
+        HttpResponce { status: self.status, headers: self.headers, content: self.content, stream: self.stream }
     }
 }
 
@@ -90,19 +350,20 @@ impl HttpResponceBuilder {
 pub struct HttpResponce {
     status: Status,
     headers: Headers,
-    content: Content
+    content: Content,
+    stream: Option<ContentStream>
 }
 
 impl HttpResponce {
     fn ok() -> Self {
-        HttpResponce { status: Status::OK, headers: HashMap::new(), content: None }
+        HttpResponce { status: Status::OK, headers: Headers::new(), content: None, stream: None }
     }
 
     fn with_code(status: Status) -> Self {
-        HttpResponce { status, headers: HashMap::new(), content: None }
+        HttpResponce { status, headers: Headers::new(), content: None, stream: None }
     }
 
-    fn parse(responce: String) -> Result<Self> {
+    pub fn parse(responce: String) -> Result<Self> {
         let splited: Vec<&str> = responce.lines().collect();
         let separator_line = splited
             .iter()
@@ -110,7 +371,35 @@ impl HttpResponce {
             .ok_or(Error::MalformedResponce)?
         ;
 
-        let mut status_line = splited[0].split(" ");
+        let (status, mut headers) = Self::parse_head(&splited[..separator_line])?;
+
+        let content: Option<String> = if headers.is_chunked() {
+            let (_, raw_content) = responce
+                .split_once("\r\n\r\n")
+                .ok_or(Error::MalformedResponce)?
+            ;
+            let (content, trailers) = chunked::decode(&mut raw_content.as_bytes(), usize::MAX)
+                .map_err(|_| Error::MalformedResponce)?
+            ;
+            headers.extend(trailers);
+
+            Some(content)
+        } else {
+            (splited.len() > separator_line + 1)
+            .then(|| 
+                splited[separator_line+1..].join("")
+            )
+        };
+
+        Ok(Self { status, headers, content, stream: None })
+    }
+
+    /// Разбирает строку состояния и заголовки
+    fn parse_head(head: &[&str]) -> Result<(Status, Headers)> {
+        let mut status_line = head
+            .first()
+            .ok_or(Error::MalformedResponce)?
+            .splitn(3, " ");
 
         status_line.nth(0)
             .ok_or(Error::MalformedResponce)?
@@ -124,53 +413,159 @@ impl HttpResponce {
             .ok_or(Error::MalformedStatusLine)?
             .try_into()?
         ;
+        let status = match status {
+            Status::Custom(code, _) => Status::Custom(
+                code,
+                status_line.nth(0).unwrap_or_default().to_string()
+            ),
+            status => status,
+        };
 
         let mut headers: Headers = Headers::new();
-        for header in splited[1..separator_line].to_vec() {
-            let mut split = header.split(": ");
-            let (k,v) =
-                (
-                    split.nth(0).ok_or(Error::MalformedHeader)?,
-                    split.nth(0).ok_or(Error::MalformedHeader)?,
-                );
-            headers.insert(k.into(), v.into());
+        for header in &head[1..] {
+            let (k,v) = Headers::parse_line(header)?;
+            headers.append(k, v);
         };
 
-        let content: Option<String> = 
-            (splited.len() > separator_line + 1)
-            .then(|| 
-                splited[separator_line+1..].join("")
-            )
-        ;
-
-        Ok(Self { status, headers, content})
+        Ok((status, headers))
     }
 
-    pub fn serialize(self) -> String {
+    fn serialize_head(&self) -> String {
         let stauts_line = format!("HTTP/1.1 {}",self.status);
-        let headers = self.headers.into_iter()
-            .map(|(k,v)| [SEPARATOR,[k,v].join(": ").as_str()].join(""))
-            .collect::<Vec<String>>()
-            .join("")
-        ;
-
-        let content = self.content.unwrap_or("".to_string());
+        let headers = self.headers.serialize();
 
-        format!("{}{}\r\n\r\n{}",
+        format!("{}{}\r\n\r\n",
             stauts_line,
-            headers,
+            headers
+        )
+    }
+
+    pub fn serialize(self) -> String {
+        let head = self.serialize_head();
+
+        let content = match self.stream {
+            Some(stream) => stream.0
+                .filter(|chunk| !chunk.is_empty())
+                .map(|chunk| chunked::encode_chunk(&chunk))
+                .chain([chunked::LAST_CHUNK.to_string()])
+                .collect(),
+            None => self.content.unwrap_or("".to_string()),
+        };
+
+        format!("{}{}",
+            head,
             content
         )
     }
 
-    const fn headers(&self) -> &Headers { &self.headers }
-    const fn content(&self) -> &Content { &self.content }
+    /// Записывает ответ в поток. Содержание, заданное через
+    /// [`HttpResponceBuilder::chunked`], отправляется по блоку за раз
+    pub fn write_to(mut self, writer: &mut impl Write) -> std::io::Result<()> {
+        let Some(stream) = self.stream.take() else {
+            return writer.write_all(self.serialize().as_bytes());
+        };
+
+        writer.write_all(self.serialize_head().as_bytes())?;
+        for chunk in stream.0.filter(|chunk| !chunk.is_empty()) {
+            writer.write_all(chunked::encode_chunk(&chunk).as_bytes())?;
+            writer.flush()?;
+        }
+        writer.write_all(chunked::LAST_CHUNK.as_bytes())?;
+        writer.flush()
+    }
+
+    /// Читает ответ на запрос методом `method` из потока.
+    ///
+    /// Длина содержания определяется по `Transfer-Encoding: chunked`,
+    /// `Content-Length` или, если их нет, по закрытию соединения.
+    /// Содержание больше `content_limit` байт отклоняется с [`Error::ContentTooLarge`]
+    pub fn read(reader: &mut impl BufRead, method: Method, content_limit: usize) -> Result<Self> {
+        let head: Vec<String> = reader
+            .by_ref()
+            .lines()
+            .map(|s|
+                s.map_err(|_| Error::MalformedResponce)
+            )
+            .take_while(|line|
+                line.as_ref().is_ok_and(|s| !s.is_empty())
+            )
+            .collect::<Result<Vec<String>>>()?
+        ;
+        let head: Vec<&str> = head.iter().map(|l| l.as_str()).collect();
+        let (status, headers) = Self::parse_head(&head)?;
+
+        let mut responce = Self { status, headers, content: None, stream: None };
+
+        let without_content = method == Method::HEAD
+            || responce.status.is_informational()
+            || matches!(responce.status, Status::NoContent | Status::NotModified);
+        if without_content {
+            return Ok(responce);
+        }
+
+        if responce.headers.is_chunked() {
+            let (content, trailers) = chunked::decode(reader, content_limit)
+                .map_err(|e| match e {
+                    Error::ContentTooLarge => e,
+                    _ => Error::MalformedResponce,
+                })?
+            ;
+            responce.headers.extend(trailers);
+            responce.content = Some(content);
+
+            return Ok(responce);
+        }
+
+        let content = match responce.headers.content_length()? {
+            Some(length) if length > content_limit => return Err(Error::ContentTooLarge),
+            Some(length) => {
+                let mut content = vec![0; length];
+                reader
+                    .read_exact(&mut content)
+                    .map_err(|_| Error::MalformedResponce)?
+                ;
+
+                content
+            },
+            None => {
+                let mut content = vec![];
+                reader
+                    .take(content_limit as u64 + 1)
+                    .read_to_end(&mut content)
+                    .map_err(|_| Error::MalformedResponce)?
+                ;
+
+                if content.len() > content_limit {
+                    return Err(Error::ContentTooLarge);
+                }
+
+                content
+            },
+        };
+
+        if !content.is_empty() {
+            responce.content = Some(
+                String::from_utf8(content)
+                    .map_err(|_| Error::MalformedResponce)?
+            );
+        }
+
+        Ok(responce)
+    }
+
+    pub fn set_header(&mut self, key: String, value: String) {
+        self.headers.insert(key, value);
+    }
+
+    pub const fn status(&self) -> &Status { &self.status }
+    pub const fn headers(&self) -> &Headers { &self.headers }
+    pub const fn content(&self) -> &Content { &self.content }
 
 }
 
 type RequestTarget = String;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     GET,
     HEAD,
@@ -251,20 +646,34 @@ impl HttpRequestBuilder {
 
     pub fn content(mut self, content: &dyn ToString) -> Self {
         let content = content.to_string();
-        self.headers.insert("Content-Length".to_string(), content.len().to_string());
+        self.headers.set_content_length(content.len());
         self.content = Some(content);
         self
     }
 
+    /// Если путь содержит неверные `%` последовательности,
+    /// он остается как есть, а параметры запроса не разбираются
     pub fn build(self) -> HttpRequest {
-        HttpRequest { method: self.method, path: self.path, headers: self.headers, content: self.content }
+        let (path, query) = uri::split_target(&self.path)
+            .unwrap_or_else(|_| (self.path.clone(), Query::new()));
+
+        HttpRequest {
+            method: self.method,
+            target: self.path,
+            path,
+            query,
+            headers: self.headers,
+            content: self.content
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HttpRequest {
     method: Method,
-    path: RequestTarget,
+    target: RequestTarget,
+    path: String,
+    query: Query,
     headers: Headers,
     content: Content    
 }
@@ -272,6 +681,7 @@ pub struct HttpRequest {
 impl HttpRequest {
     /// Преобразует строку в [`HttpRequest`].
     /// Поле `content` будет `None`, содержание запроса необходимо обработать самостоятельно
+    /// (см. [`HttpRequest::read`])
     pub fn parse(request: Vec<String>) -> Result<Self> {
         let mut request = request.into_iter();
 
@@ -286,10 +696,11 @@ impl HttpRequest {
             .try_into()?
         ;
 
-        let path = status_line.nth(0)
+        let target = status_line.nth(0)
             .ok_or(Error::MalformedRequest)?
             .to_string()
         ;
+        let (path, query) = uri::split_target(&target)?;
 
         status_line.nth(0)
             .ok_or(Error::MalformedRequest)?
@@ -300,25 +711,16 @@ impl HttpRequest {
 
         let mut headers: Headers = Headers::new();
         for header in request.take_while(|l| !l.is_empty()) {
-            let mut split = header.split(": ");
-            let (k,v) =
-                (
-                    split.nth(0).ok_or(Error::MalformedHeader)?,
-                    split.nth(0).ok_or(Error::MalformedHeader)?,
-                );
-            headers.insert(k.into(), v.into());
+            let (k,v) = Headers::parse_line(&header)?;
+            headers.append(k, v);
         };
 
-        Ok(Self { method, path, headers, content: None})
+        Ok(Self { method, target, path, query, headers, content: None})
     }
 
     pub fn serialize(self) -> String {
-        let stauts_line = format!("{} {} {}",self.method, self.path,HTTP_VERSION);
-        let headers  = self.headers.into_iter()
-            .map(|(k,v)| [SEPARATOR,[k,v].join(": ").as_str()].join(""))
-            .collect::<Vec<String>>()
-            .join("")
-        ;
+        let stauts_line = format!("{} {} {}",self.method, self.target,HTTP_VERSION);
+        let headers = self.headers.serialize();
 
         let content = self.content.unwrap_or("".to_string());
 
@@ -330,29 +732,103 @@ impl HttpRequest {
     }
 
     pub const fn method(&self) -> &Method { &self.method }
-    pub const fn path(&self) -> &RequestTarget { &self.path }
+    /// Цель запроса в том виде, в котором она пришла
+    pub const fn target(&self) -> &RequestTarget { &self.target }
+    /// Декодированный путь без параметров запроса
+    pub const fn path(&self) -> &String { &self.path }
+    pub const fn queries(&self) -> &Query { &self.query }
     pub const fn headers(&self) -> &Headers { &self.headers }
     pub const fn content(&self) -> &Content { &self.content }
 }
 
-impl TryFrom<&TcpStream> for HttpRequest {
-    type Error = crate::Error;
-
-    fn try_from(value: &TcpStream) -> std::result::Result<Self, Self::Error> {
-        let mut buf_reader = BufReader::new(value);
-
-        let mut request: Vec<String> = buf_reader
+impl HttpRequest {
+    /// Читает запрос из потока: стартовую строку, заголовки
+    /// и ровно `Content-Length` байт содержания,
+    /// либо содержание в блоках, если указан `Transfer-Encoding: chunked`.
+    ///
+    /// Запрос с содержанием больше `content_limit` байт
+    /// отклоняется с [`Error::ContentTooLarge`], содержание при этом не читается.
+    pub fn read(reader: &mut impl BufRead, content_limit: usize) -> Result<Self> {
+        let request: Vec<String> = reader
+            .by_ref()
             .lines()
-            .map(|s| 
+            .map(|s|
                 s.map_err(|_| Error::MalformedRequest)
-            ) // Fix this
+            )
             .take_while(|line|
                 line.as_ref().is_ok_and(|s| !s.is_empty())
             )
             .collect::<Result<Vec<String>>>()?
         ;
 
-        Ok(Self::parse(request)?)
+        let mut request = Self::parse(request)?;
+
+        if request.headers.is_chunked() {
+            let (content, trailers) = chunked::decode(reader, content_limit)?;
+            request.headers.extend(trailers);
+            request.content = Some(content);
+
+            return Ok(request);
+        }
+
+        let content_length: usize = request.headers
+            .content_length()?
+            .unwrap_or(0)
+        ;
+
+        if content_length > content_limit {
+            return Err(Error::ContentTooLarge);
+        }
+
+        if content_length > 0 {
+            let mut content = vec![0; content_length];
+            reader
+                .read_exact(&mut content)
+                .map_err(|_| Error::MalformedRequest)?
+            ;
+
+            request.content = Some(
+                String::from_utf8(content)
+                    .map_err(|_| Error::MalformedRequest)?
+            );
+        }
+
+        Ok(request)
+    }
+
+    /// Первое значение параметра запроса `key`
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .get(key)
+            .and_then(|v| v.first())
+            .map(|v| v.as_str())
+    }
+
+    /// Все значения параметра запроса `key` в порядке следования
+    pub fn query_all(&self, key: &str) -> &[String] {
+        self.query
+            .get(key)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Должно ли соединение остаться открытым после ответа на этот запрос.
+    /// В HTTP/1.1 соединение постоянное, если клиент не прислал `Connection: close`
+    pub fn keep_alive(&self) -> bool {
+        !self.headers
+            .connection()
+            .iter()
+            .any(|o| o == "close")
+    }
+}
+
+impl TryFrom<&TcpStream> for HttpRequest {
+    type Error = crate::Error;
+
+    fn try_from(value: &TcpStream) -> std::result::Result<Self, Self::Error> {
+        let mut buf_reader = BufReader::new(value);
+
+        Self::read(&mut buf_reader, DEFAULT_CONTENT_LIMIT)
     }
 
 }
@@ -368,9 +844,13 @@ pub enum Error {
 
     UnsopportedProtocol,
     MalformedHeader,
+    ContentTooLarge,
+
+    ConnectionError(std::io::Error),
+    TooManyRedirects,
 
     ResponceError(HttpResponce),
-    InternalServerError(Box<dyn std::error::Error>)
+    InternalServerError(String)
 }
 
 impl Display for Error {
@@ -383,11 +863,10 @@ impl Display for Error {
             Error::MalformedRequest => "Error during parsing request",
             Error::UnknownRequestMethod => "Unknown request method",
             Error::UnsopportedProtocol => "Unsopported HTTP protocol",
-            Error::InternalServerError(e) => {
-                let message = format!("Internal server error: {}",e);
-
-                Box::leak(message.into_boxed_str())
-            },
+            Error::ContentTooLarge => "Request content is too large",
+            Error::ConnectionError(e) => return write!(f, "Connection error: {e}"),
+            Error::TooManyRedirects => "Too many redirects",
+            Error::InternalServerError(message) => return write!(f, "Internal server error: {message}"),
             Error::ResponceError(_) => "Error made into responce",
         };
 
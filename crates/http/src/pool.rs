@@ -0,0 +1,137 @@
+//! Пул потоков для параллельной обработки соединений
+use std::{io::ErrorKind, net::{TcpListener, TcpStream}, sync::{mpsc::{self, Receiver, SyncSender, TrySendError}, Arc, Mutex}, thread::{self, JoinHandle}, time::Duration};
+
+use crate::{shutdown, HttpResponce, HttpResponceBuilder, Status};
+
+/// Как часто [`ThreadPool::serve`] проверяет, не пора ли завершаться
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Пул из фиксированного числа потоков, которые обрабатывают задачи типа `T`
+/// общим обработчиком.
+///
+/// Задачи, которые ещё не взял ни один поток, ждут в очереди ограниченной длины.
+/// При удалении пула очередь дорабатывается до конца
+pub struct ThreadPool<T: Send + 'static> {
+    workers: Vec<JoinHandle<()>>,
+    sender: Option<SyncSender<T>>
+}
+
+impl<T: Send + 'static> ThreadPool<T> {
+    /// Создает пул из `workers` потоков и очередью на `queue_limit` задач
+    pub fn new(workers: usize, queue_limit: usize, handler: impl Fn(T) + Send + Sync + 'static) -> Self {
+        assert!(workers > 0, "Thread pool requires at least one worker");
+
+        let (sender, receiver) = mpsc::sync_channel::<T>(queue_limit);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handler = Arc::new(handler);
+
+        let workers = (0..workers)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let handler = Arc::clone(&handler);
+
+                thread::spawn(move || work(receiver, handler))
+            })
+            .collect()
+        ;
+
+        Self { workers, sender: Some(sender) }
+    }
+
+    /// Ставит задачу в очередь. Если очередь заполнена, задача возвращается обратно
+    pub fn execute(&self, job: T) -> std::result::Result<(), T> {
+        let sender = self.sender
+            .as_ref()
+            .expect("Thread pool is shut down")
+        ;
+
+        match sender.try_send(job) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(job) | TrySendError::Disconnected(job)) => Err(job),
+        }
+    }
+
+    /// Перестает принимать задачи и ждет, пока потоки обработают очередь
+    pub fn join(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.sender.take());
+
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                eprintln!("Thread pool worker panicked");
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for ThreadPool<T> {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn work<T>(receiver: Arc<Mutex<Receiver<T>>>, handler: Arc<impl Fn(T)>) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+
+        match job {
+            Ok(job) => handler(job),
+            // Очередь закрыта и пуста
+            Err(_) => return,
+        }
+    }
+}
+
+impl ThreadPool<TcpStream> {
+    /// Принимает соединения и раздает их потокам пула,
+    /// пока не будет запрошено завершение (см. [`shutdown`]).
+    ///
+    /// Если очередь заполнена, клиенту сразу отвечают 503.
+    /// Перед возвратом дожидается обработки уже принятых соединений
+    pub fn serve(self, listener: &TcpListener) -> std::io::Result<()> {
+        listener.set_nonblocking(true)?;
+
+        while !shutdown::requested() {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                    continue;
+                },
+                Err(e) => {
+                    eprintln!("Failed to accept connection: {e}");
+                    continue;
+                },
+            };
+
+            if let Err(e) = stream.set_nonblocking(false) {
+                eprintln!("Failed to configure connection: {e}");
+                continue;
+            }
+
+            if let Err(mut stream) = self.execute(stream) {
+                if let Err(e) = overloaded().write_to(&mut stream) {
+                    eprintln!("Error writing stream: {e}");
+                }
+            }
+        }
+
+        self.join();
+
+        Ok(())
+    }
+}
+
+fn overloaded() -> HttpResponce {
+    HttpResponceBuilder::default()
+        .status(Status::ServiceUnavailable)
+        .header("Retry-After".to_string(), "1".to_string())
+        .header("Connection".to_string(), "close".to_string())
+        .build()
+}
@@ -0,0 +1,168 @@
+//! Маршрутизация запросов по методу и шаблону пути
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{uri, Error, HttpRequest, HttpResponce, HttpResponceBuilder, Method, Result, Status};
+
+/// Параметры пути, извлеченные по шаблону маршрута
+#[derive(Debug, Default)]
+pub struct Params(HashMap<String,String>);
+
+impl Params {
+    /// Читает параметр `name` и преобразует его в `T`.
+    /// Если значение не преобразуется, возвращает [`Error::MalformedRequest`]
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<T> {
+        self.0
+            .get(name)
+            .ok_or(Error::InternalServerError(
+                format!("Unknown path parameter '{name}'")
+            ))?
+            .parse()
+            .map_err(|_| Error::MalformedRequest)
+    }
+
+    /// Декодированное значение параметра без преобразования
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(|s| s.as_str())
+    }
+}
+
+type Handler<S> = Box<dyn Fn(HttpRequest, Params, &S) -> Result<HttpResponce> + Send + Sync>;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// `:name` или `:name.ext`, во втором случае сегмент пути
+    /// должен оканчиваться на `.ext`, а в параметр попадает остальное
+    Param { name: String, suffix: String },
+}
+
+struct Route<S> {
+    method: Method,
+    pattern: Vec<Segment>,
+    handler: Handler<S>
+}
+
+impl<S> Route<S> {
+    /// Сравнивает шаблон с сегментами пути до декодирования, чтобы `%2F`
+    /// не делил сегмент. Параметры декодируются после сравнения
+    fn matches(&self, path: &[&str]) -> Option<Params> {
+        if self.pattern.len() != path.len() {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+        for (segment, part) in self.pattern.iter().zip(path) {
+            match segment {
+                Segment::Literal(literal) if literal == part => (),
+                Segment::Literal(_) => return None,
+                Segment::Param { name, suffix } => {
+                    let value = part.strip_suffix(suffix.as_str()).filter(|v| !v.is_empty())?;
+                    params.insert(name.clone(), uri::percent_decode(value, false).ok()?);
+                },
+            }
+        }
+
+        Some(Params(params))
+    }
+}
+
+/// Набор маршрутов вида `/house/floor/:floor/room/:room`,
+/// где сегменты с `:` становятся параметрами пути.
+/// Маршруты проверяются в порядке добавления, поэтому `/:floor.svg`
+/// нужно добавлять раньше `/:floor`.
+///
+/// `S` - состояние приложения, которое передается каждому обработчику
+pub struct Router<S> {
+    routes: Vec<Route<S>>
+}
+
+impl<S> Default for Router<S> {
+    fn default() -> Self {
+        Self { routes: vec![] }
+    }
+}
+
+impl<S> Router<S> {
+    pub fn route(
+        mut self,
+        method: Method,
+        pattern: &str,
+        handler: impl Fn(HttpRequest, Params, &S) -> Result<HttpResponce> + Send + Sync + 'static
+    ) -> Self {
+        let pattern = split_path(pattern)
+            .into_iter()
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => {
+                    let (name, suffix) = match param.find('.') {
+                        Some(dot) => param.split_at(dot),
+                        None => (param, ""),
+                    };
+                    Segment::Param { name: name.to_string(), suffix: suffix.to_string() }
+                },
+                None => Segment::Literal(segment.to_string()),
+            })
+            .collect()
+        ;
+
+        self.routes.push(Route { method, pattern, handler: Box::new(handler) });
+        self
+    }
+
+    /// Передает запрос обработчику подходящего маршрута.
+    ///
+    /// Если путь не совпал ни с одним шаблоном, отвечает 404.
+    /// Если путь совпал, но метод не поддерживается, отвечает 405
+    /// с заголовком `Allow`
+    pub fn handle(&self, request: HttpRequest, state: &S) -> Result<HttpResponce> {
+        let target = request.target().clone();
+        let path = target
+            .split_once('?')
+            .map_or(target.as_str(), |(path, _)| path);
+        let path = split_path(path);
+
+        let mut allowed: Vec<Method> = vec![];
+
+        for route in &self.routes {
+            let Some(params) = route.matches(&path) else {
+                continue;
+            };
+
+            if route.method == *request.method() {
+                return (route.handler)(request, params, state);
+            }
+
+            if !allowed.contains(&route.method) {
+                allowed.push(route.method);
+            }
+        }
+
+        if allowed.is_empty() {
+            return Ok(
+                HttpResponceBuilder::default()
+                    .status(Status::NotFound)
+                    .build()
+            );
+        }
+
+        let allowed = allowed
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+        ;
+
+        Ok(
+            HttpResponceBuilder::default()
+                .status(Status::MethodNotAllowed)
+                .header("Allow".to_string(), allowed)
+                .build()
+        )
+    }
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect()
+}
@@ -0,0 +1,38 @@
+//! Запрос на корректное завершение сервера по SIGTERM и SIGINT
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Было ли запрошено завершение
+pub fn requested() -> bool {
+    SHUTDOWN.load(Ordering::SeqCst)
+}
+
+/// Запрашивает завершение вручную
+pub fn request() {
+    SHUTDOWN.store(true, Ordering::SeqCst)
+}
+
+/// Устанавливает обработчики SIGTERM и SIGINT, которые запрашивают завершение
+#[cfg(unix)]
+pub fn listen() {
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    extern "C" fn handler(_signum: i32) {
+        request()
+    }
+
+    // SAFETY: обработчик только меняет атомарный флаг
+    unsafe {
+        signal(SIGTERM, handler);
+        signal(SIGINT, handler);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn listen() {}
@@ -2,7 +2,7 @@ use crate::{HttpRequest, HttpRequestBuilder, HttpResponce, HttpResponceBuilder};
 
 impl PartialEq for HttpResponce {
     fn eq(&self, other: &Self) -> bool {
-        self.status as usize == other.status as usize &&
+        self.status == other.status &&
         self.headers == other.headers &&
         self.content == other.content
     }
@@ -11,7 +11,9 @@ impl PartialEq for HttpResponce {
 impl PartialEq for HttpRequest {
     fn eq(&self, other: &Self) -> bool {
         self.method as usize == other.method as usize &&
+        self.target == other.target &&
         self.path == other.path &&
+        self.query == other.query &&
         self.headers == other.headers &&
         self.content == other.content
     }
@@ -111,3 +113,373 @@ fn request_parse() {
     
     assert_eq!(resp,expected);
 }
+
+#[test]
+fn request_read_content() {
+    let raw = "POST /aboba HTTP/1.1\r\nContent-Length: 5\r\n\r\nAbobaGET / HTTP/1.1\r\n\r\n";
+    let mut reader = raw.as_bytes();
+
+    let request = HttpRequest::read(&mut reader, crate::DEFAULT_CONTENT_LIMIT).unwrap();
+
+    let expected = HttpRequestBuilder::new()
+        .method(crate::Method::POST)
+        .content(&"Aboba")
+        .path(&"/aboba")
+        .build()
+    ;
+
+    assert_eq!(request,expected);
+
+    let request = HttpRequest::read(&mut reader, crate::DEFAULT_CONTENT_LIMIT).unwrap();
+
+    assert_eq!(request,HttpRequestBuilder::new().build());
+}
+
+#[test]
+fn request_content_too_large() {
+    let raw = "POST /aboba HTTP/1.1\r\nContent-Length: 5\r\n\r\nAboba";
+
+    let request = HttpRequest::read(&mut raw.as_bytes(), 4);
+    assert!(matches!(request, Err(crate::Error::ContentTooLarge)));
+
+    let raw = "POST /aboba HTTP/1.1\r\nContent-Length: boba\r\n\r\nAboba";
+
+    let request = HttpRequest::read(&mut raw.as_bytes(), 4);
+    assert!(matches!(request, Err(crate::Error::MalformedHeader)));
+}
+
+#[test]
+fn request_read_chunked() {
+    let raw = "POST /aboba HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nAboba\r\n6;ext=1\r\n boba!\r\n0\r\nExpires: never\r\n\r\n";
+
+    let request = HttpRequest::read(&mut raw.as_bytes(), crate::DEFAULT_CONTENT_LIMIT).unwrap();
+
+    let expected = HttpRequestBuilder::new()
+        .method(crate::Method::POST)
+        .header("Transfer-Encoding".to_string(), "chunked".to_string())
+        .header("Expires".to_string(), "never".to_string())
+        .path(&"/aboba")
+        .build()
+    ;
+    let expected = HttpRequest { content: Some("Aboba boba!".to_string()), ..expected };
+
+    assert_eq!(request,expected);
+
+    let request = HttpRequest::read(&mut raw.as_bytes(), 8);
+    assert!(matches!(request, Err(crate::Error::ContentTooLarge)));
+
+    let raw = "POST /aboba HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nZ\r\nAboba\r\n0\r\n\r\n";
+    assert!(HttpRequest::read(&mut raw.as_bytes(), crate::DEFAULT_CONTENT_LIMIT).is_err());
+}
+
+#[test]
+fn chunked_huge_size() {
+    // Сумма с уже прочитанным содержанием переполняла usize
+    let raw = "POST /aboba HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\nFFFFFFFFFFFFFFFF\r\nboba\r\n0\r\n\r\n";
+    let request = HttpRequest::read(&mut raw.as_bytes(), crate::DEFAULT_CONTENT_LIMIT);
+    assert!(matches!(request, Err(crate::Error::ContentTooLarge)));
+
+    // Без ограничения объявленный размер не выделяется, а блок просто обрывается
+    let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\nFFFFFFFFFFFFFFF0\r\nboba\r\n0\r\n\r\n";
+    assert!(HttpResponce::parse(raw.to_string()).is_err());
+
+    // Размер блока - только шестнадцатеричные цифры, без знака
+    let raw = "POST /aboba HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n+a\r\n0123456789\r\n0\r\n\r\n";
+    let request = HttpRequest::read(&mut raw.as_bytes(), crate::DEFAULT_CONTENT_LIMIT);
+    assert!(matches!(request, Err(crate::Error::MalformedRequest)));
+}
+
+#[test]
+fn responce_chunked_round_trip() {
+    let chunks = vec!["Boba ".to_string(), "".to_string(), "boba aboba".to_string()];
+
+    let resp = HttpResponceBuilder::default()
+        .chunked(chunks.into_iter())
+        .build()
+        .serialize();
+
+    let expected = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nBoba \r\nA\r\nboba aboba\r\n0\r\n\r\n".to_string();
+
+    assert_eq!(resp,expected);
+
+    let parsed = HttpResponce::parse(resp).unwrap();
+
+    assert_eq!(parsed.content, Some("Boba boba aboba".to_string()));
+}
+
+#[test]
+fn responce_chunked_write_to() {
+    let mut written: Vec<u8> = vec![];
+
+    HttpResponceBuilder::default()
+        .chunked((1..=3).map(|n| n.to_string()))
+        .build()
+        .write_to(&mut written)
+        .unwrap();
+
+    let expected = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\n1\r\n1\r\n2\r\n1\r\n3\r\n0\r\n\r\n";
+
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+}
+
+#[test]
+fn request_keep_alive() {
+    let raw = "GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\nConnection: Close\r\n\r\n";
+    let mut reader = raw.as_bytes();
+
+    assert!(HttpRequest::read(&mut reader, crate::DEFAULT_CONTENT_LIMIT).unwrap().keep_alive());
+    assert!(!HttpRequest::read(&mut reader, crate::DEFAULT_CONTENT_LIMIT).unwrap().keep_alive());
+}
+
+#[test]
+fn thread_pool_queue_limit() {
+    use std::sync::{mpsc, Arc, Mutex};
+
+    let (started, started_receiver) = mpsc::channel::<()>();
+    let (release, release_receiver) = mpsc::channel::<()>();
+    let release_receiver = Arc::new(Mutex::new(release_receiver));
+    let handled = Arc::new(Mutex::new(vec![]));
+
+    let pool = {
+        let handled = Arc::clone(&handled);
+
+        crate::pool::ThreadPool::new(1, 1, move |job: usize| {
+            started.send(()).unwrap();
+            release_receiver.lock().unwrap().recv().unwrap();
+            handled.lock().unwrap().push(job);
+        })
+    };
+
+    // Первая задача занимает поток, вторая ждет в очереди, третьей места нет
+    pool.execute(1).unwrap();
+    started_receiver.recv().unwrap();
+    pool.execute(2).unwrap();
+    assert_eq!(pool.execute(3), Err(3));
+
+    release.send(()).unwrap();
+    release.send(()).unwrap();
+    pool.join();
+
+    assert_eq!(*handled.lock().unwrap(), vec![1, 2]);
+}
+
+#[test]
+fn router_params_and_status() {
+    use crate::{router::Router, Method, Status};
+
+    let router: Router<usize> = Router::default()
+        .route(Method::GET, "/house/floor/:floor/room/:room", |_, params, offset| {
+            let floor: usize = params.get("floor")?;
+            let room: usize = params.get("room")?;
+
+            Ok(HttpResponceBuilder::default().content(&(floor + room + offset)).build())
+        })
+        .route(Method::DELETE, "/house/floor/:floor/room/:room", |_, _, _| {
+            Ok(HttpResponceBuilder::default().build())
+        })
+        .route(Method::GET, "/house/floor/:floor.svg", |_, params, _| {
+            Ok(HttpResponceBuilder::default().content(&params.raw("floor").unwrap_or_default()).build())
+        })
+        .route(Method::GET, "/house/device/:device", |_, params, _| {
+            Ok(HttpResponceBuilder::default().content(&params.raw("device").unwrap_or_default()).build())
+        });
+
+    let request = |method, path: &str| HttpRequestBuilder::new()
+        .method(method)
+        .path(&path)
+        .build();
+
+    let resp = router.handle(request(Method::GET, "/house/floor/1/room/2"), &10).unwrap();
+    assert_eq!(resp.content, Some("13".to_string()));
+
+    let resp = router.handle(request(Method::GET, "/house/floor/one/room/2"), &10);
+    assert!(matches!(resp, Err(crate::Error::MalformedRequest)));
+
+    let resp = router.handle(request(Method::POST, "/house/floor/1/room/2"), &10).unwrap();
+    assert!(matches!(resp.status, Status::MethodNotAllowed));
+    assert_eq!(resp.headers.get("Allow"), Some("GET, DELETE"));
+
+    let resp = router.handle(request(Method::GET, "/house/floor/1"), &10).unwrap();
+    assert!(matches!(resp.status, Status::NotFound));
+
+    let resp = router.handle(request(Method::GET, "/house/floor/2.svg"), &10).unwrap();
+    assert_eq!(resp.content, Some("2".to_string()));
+
+    let resp = router.handle(request(Method::GET, "/house/floor/.svg"), &10).unwrap();
+    assert!(matches!(resp.status, Status::NotFound));
+
+    // `%2F` остается внутри сегмента и декодируется только в параметре
+    let resp = router.handle(request(Method::GET, "/house/device/hall%2Fsmoke%20alarm?x=1"), &10).unwrap();
+    assert_eq!(resp.content, Some("hall/smoke alarm".to_string()));
+
+    let resp = router.handle(request(Method::GET, "/house/floor/1%2Froom/2"), &10).unwrap();
+    assert!(matches!(resp.status, Status::NotFound));
+}
+
+#[test]
+fn request_query_and_percent_decoding() {
+    let resp = "GET /house/room%20name?floor=1&tag=a+b&tag=%D0%B0&flag HTTP/1.1\r\n\r\n".to_string();
+    let resp = HttpRequest::parse(
+        resp
+        .lines()
+        .map(|s| s.to_string())
+        .collect()
+    ).unwrap();
+
+    assert_eq!(resp.path(), "/house/room name");
+    assert_eq!(resp.query("floor"), Some("1"));
+    assert_eq!(resp.query_all("tag"), ["a b".to_string(), "а".to_string()]);
+    assert_eq!(resp.query("flag"), Some(""));
+    assert_eq!(resp.query("missing"), None);
+    assert!(resp.query_all("missing").is_empty());
+
+    for target in ["/house%2", "/house%zz", "/house%+F", "/house?floor=%FF", "/house?floor=%+F"] {
+        let resp = format!("GET {target} HTTP/1.1");
+        assert!(matches!(HttpRequest::parse(vec![resp]), Err(crate::Error::MalformedRequest)));
+    }
+}
+
+#[test]
+fn responce_parse_status() {
+    use crate::Status;
+
+    let resp = "HTTP/1.1 204 No Content\r\n\r\n".to_string();
+    let parsed = HttpResponce::parse(resp).unwrap();
+    assert_eq!(parsed.status, Status::NoContent);
+    assert!(parsed.status.is_success());
+
+    let resp = "HTTP/1.1 429 Too Many Requests\r\n\r\n".to_string();
+    let parsed = HttpResponce::parse(resp).unwrap();
+    assert_eq!(parsed.status, Status::TooManyRequests);
+    assert!(parsed.status.is_client_error());
+
+    let resp = "HTTP/1.1 599 Network Connect Timeout Error\r\n\r\n".to_string();
+    let parsed = HttpResponce::parse(resp).unwrap();
+    assert_eq!(parsed.status, Status::Custom(599, "Network Connect Timeout Error".to_string()));
+    assert!(parsed.status.is_server_error());
+    assert_eq!(parsed.status.to_string(), "599 Network Connect Timeout Error");
+
+    let resp = HttpResponceBuilder::default()
+        .status(Status::Custom(404, "Room not found".to_string()))
+        .build()
+        .serialize();
+    assert_eq!(resp, "HTTP/1.1 404 Room not found\r\n\r\n");
+
+    for resp in ["HTTP/1.1 20 OK\r\n\r\n", "HTTP/1.1 2000 OK\r\n\r\n", "HTTP/1.1 abc OK\r\n\r\n", "HTTP/1.1 099 OK\r\n\r\n"] {
+        assert!(HttpResponce::parse(resp.to_string()).is_err());
+    }
+}
+
+#[test]
+fn headers_case_insensitive_and_ordered() {
+    let raw = "POST /aboba HTTP/1.1\r\nhost: localhost\r\ncontent-length: 5\r\nAccept: text/html\r\naccept: application/json\r\nConnection:close\r\n\r\nAboba";
+
+    let request = HttpRequest::read(&mut raw.as_bytes(), crate::DEFAULT_CONTENT_LIMIT).unwrap();
+
+    assert_eq!(request.content(), &Some("Aboba".to_string()));
+    assert_eq!(request.headers().content_length().unwrap(), Some(5));
+    assert_eq!(request.headers().get("Host"), Some("localhost"));
+    assert_eq!(
+        request.headers().get_all("ACCEPT").collect::<Vec<&str>>(),
+        vec!["text/html", "application/json"]
+    );
+    assert_eq!(request.headers().connection(), vec!["close".to_string()]);
+    assert!(!request.keep_alive());
+
+    let resp = HttpResponceBuilder::default()
+        .header("Content-Type".to_string(), "text/plain".to_string())
+        .append_header("Set-Cookie".to_string(), "a=1".to_string())
+        .append_header("Set-Cookie".to_string(), "b=2".to_string())
+        .header("content-type".to_string(), "application/json".to_string())
+        .content(&"{}")
+        .build()
+        .serialize();
+
+    let expected = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nContent-Length: 2\r\n\r\n{}";
+
+    assert_eq!(resp, expected);
+}
+
+#[test]
+fn client_follows_redirect() {
+    use std::{io::{BufReader, Write}, net::TcpListener, thread};
+
+    use crate::{client::Client, Method, Status};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let mut paths = vec![];
+
+        for responce in [
+            HttpResponceBuilder::default()
+                .status(Status::SeeOther)
+                .header("Location".to_string(), "/house".to_string())
+                .build(),
+            HttpResponceBuilder::default()
+                .chunked(["Boba ", "aboba"].into_iter().map(|s| s.to_string()))
+                .build(),
+        ] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = HttpRequest::read(&mut BufReader::new(&stream), crate::DEFAULT_CONTENT_LIMIT).unwrap();
+            paths.push((*request.method(), request.path().clone(), request.content().clone()));
+
+            responce.write_to(&mut stream).unwrap();
+            stream.flush().unwrap();
+        }
+
+        paths
+    });
+
+    let request = HttpRequestBuilder::new()
+        .method(Method::POST)
+        .path(&"/house/floor")
+        .header("Host".to_string(), host)
+        .content(&"Aboba")
+        .build();
+
+    let responce = Client::default().send(request).unwrap();
+
+    assert_eq!(responce.status(), &Status::OK);
+    assert_eq!(responce.content(), &Some("Boba aboba".to_string()));
+
+    let paths = server.join().unwrap();
+    assert_eq!(paths, vec![
+        (Method::POST, "/house/floor".to_string(), Some("Aboba".to_string())),
+        (Method::GET, "/house".to_string(), None),
+    ]);
+}
+
+#[test]
+fn responce_read_content() {
+    use crate::{Method, Status};
+
+    let raw = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nAboba";
+    let responce = HttpResponce::read(&mut raw.as_bytes(), Method::GET, crate::DEFAULT_CONTENT_LIMIT).unwrap();
+    assert_eq!(responce.content(), &Some("Aboba".to_string()));
+
+    let responce = HttpResponce::read(&mut raw.as_bytes(), Method::HEAD, crate::DEFAULT_CONTENT_LIMIT).unwrap();
+    assert_eq!(responce.content(), &None);
+
+    let raw = "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nBoba\r\naboba";
+    let responce = HttpResponce::read(&mut raw.as_bytes(), Method::GET, crate::DEFAULT_CONTENT_LIMIT).unwrap();
+    assert_eq!(responce.content(), &Some("Boba\r\naboba".to_string()));
+
+    let responce = HttpResponce::read(&mut raw.as_bytes(), Method::GET, 4);
+    assert!(matches!(responce, Err(crate::Error::ContentTooLarge)));
+
+    let raw = "HTTP/1.1 304 Not Modified\r\n\r\n";
+    let responce = HttpResponce::read(&mut raw.as_bytes(), Method::GET, crate::DEFAULT_CONTENT_LIMIT).unwrap();
+    assert_eq!(responce.status(), &Status::NotModified);
+}
+
+#[test]
+fn error_display() {
+    let error = crate::Error::ConnectionError(std::io::Error::new(std::io::ErrorKind::TimedOut, "read timed out"));
+    assert_eq!(error.to_string(), "Connection error: read timed out");
+    assert!(matches!(error, crate::Error::ConnectionError(e) if e.kind() == std::io::ErrorKind::TimedOut));
+
+    let error = crate::Error::InternalServerError("poisoned lock".to_string());
+    assert_eq!(error.to_string(), "Internal server error: poisoned lock");
+}
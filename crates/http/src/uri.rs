@@ -0,0 +1,68 @@
+//! Разбор цели запроса на путь и параметры запроса
+use std::collections::HashMap;
+
+use crate::{Error, Result};
+
+/// Параметры запроса. Один ключ может встречаться несколько раз
+pub type Query = HashMap<String,Vec<String>>;
+
+/// Делит цель запроса на декодированный путь и параметры запроса
+pub(crate) fn split_target(target: &str) -> Result<(String, Query)> {
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (target, ""),
+    };
+
+    let path = percent_decode(path, false)?;
+
+    let mut parsed = Query::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .unwrap_or((pair, ""))
+        ;
+
+        parsed
+            .entry(percent_decode(key, true)?)
+            .or_default()
+            .push(percent_decode(value, true)?);
+    }
+
+    Ok((path, parsed))
+}
+
+/// Декодирует `%XX` последовательности. Если `plus_as_space`,
+/// `+` заменяется на пробел, как принято в параметрах запроса
+pub(crate) fn percent_decode(value: &str, plus_as_space: bool) -> Result<String> {
+    let mut bytes = value.bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(value.len());
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'%' => {
+                let hex = [
+                    bytes.next().ok_or(Error::MalformedRequest)?,
+                    bytes.next().ok_or(Error::MalformedRequest)?,
+                ];
+                let hex = std::str::from_utf8(&hex)
+                    .map_err(|_| Error::MalformedRequest)?
+                ;
+
+                // `from_str_radix` пропускает знак, например `%+F`
+                if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(Error::MalformedRequest);
+                }
+
+                decoded.push(
+                    u8::from_str_radix(hex, 16)
+                        .map_err(|_| Error::MalformedRequest)?
+                );
+            },
+            b'+' if plus_as_space => decoded.push(b' '),
+            _ => decoded.push(byte),
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|_| Error::MalformedRequest)
+}
@@ -0,0 +1,277 @@
+//! Подключенные датчики и отправка им команд
+use std::{collections::HashMap, fmt::Display, io::Write, net::TcpStream, sync::{atomic::{AtomicU32, Ordering}, mpsc::{self, RecvTimeoutError, Sender}, Arc, Mutex}, time::Duration};
+
+use gauge::{helpers::parse_command_by_id, types::{command::{Ack, Command, Sequence}, handshake::{Hello, FEATURE_COMMANDS}}};
+
+/// Сколько по умолчанию ждать ответа датчика на команду
+pub const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum CommandError {
+    UnknownDevice(String),
+    /// Датчик не заявил [`FEATURE_COMMANDS`] при рукопожатии
+    NotSupported,
+    Invalid(String),
+    Disconnected,
+    Timeout(Duration),
+    /// Датчик отказался выполнять команду
+    Failed(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::UnknownDevice(serial) => write!(f, "device {serial} is not connected"),
+            CommandError::NotSupported => write!(f, "device does not accept commands"),
+            CommandError::Invalid(e) => write!(f, "invalid command: {e}"),
+            CommandError::Disconnected => write!(f, "device disconnected"),
+            CommandError::Timeout(timeout) => write!(f, "no acknowledgement in {} ms", timeout.as_millis()),
+            CommandError::Failed(reason) => write!(f, "failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+pub struct Device {
+    hello: Hello,
+    writer: Mutex<TcpStream>,
+    next_seq: AtomicU32,
+    /// Команды, ожидающие [`Ack`]
+    pending: Mutex<HashMap<Sequence, Sender<Ack>>>,
+}
+
+impl Device {
+    pub fn hello(&self) -> &Hello {
+        &self.hello
+    }
+
+    /// Передает ответ датчика ожидающей команде
+    pub fn acknowledge(&self, ack: Ack) {
+        let sender = self.pending
+            .lock()
+            .expect("Error locking mutex")
+            .remove(&ack.seq);
+
+        match sender {
+            Some(sender) => { let _ = sender.send(ack); },
+            None => eprintln!("Unexpected acknowledgement {} from {}", ack.seq, self.hello.serial),
+        }
+    }
+
+    fn send(&self, command: &str, timeout: Duration) -> Result<(), CommandError> {
+        if !self.hello.features.iter().any(|f| f == FEATURE_COMMANDS) {
+            return Err(CommandError::NotSupported);
+        }
+
+        let command = parse_command_by_id(&self.hello.gauge_type, command)
+            .map_err(|e| CommandError::Invalid(e.to_string()))?;
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let frame = Command { seq, command }
+            .encode()
+            .map_err(|e| CommandError::Invalid(e.to_string()))?;
+
+        let (sender, receiver) = mpsc::channel();
+        self.pending.lock().expect("Error locking mutex").insert(seq, sender);
+
+        let sent = self.writer
+            .lock()
+            .expect("Error locking mutex")
+            .write_all(&frame);
+
+        let ack = match sent {
+            Ok(()) => receiver.recv_timeout(timeout),
+            Err(_) => Err(RecvTimeoutError::Disconnected),
+        };
+        self.pending.lock().expect("Error locking mutex").remove(&seq);
+
+        match ack {
+            Ok(ack) => ack.result.map_err(CommandError::Failed),
+            Err(RecvTimeoutError::Timeout) => Err(CommandError::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(CommandError::Disconnected),
+        }
+    }
+}
+
+/// Датчики по [`DeviceId`](gauge::types::device::DeviceId) из рукопожатия
+#[derive(Clone)]
+pub struct Devices {
+    devices: Arc<Mutex<HashMap<String, Arc<Device>>>>,
+    ack_timeout: Duration,
+}
+
+impl Default for Devices {
+    fn default() -> Self {
+        Self::with_ack_timeout(ACK_TIMEOUT)
+    }
+}
+
+impl Devices {
+    pub fn with_ack_timeout(ack_timeout: Duration) -> Self {
+        Self { devices: Default::default(), ack_timeout }
+    }
+
+    /// Регистрирует датчик после рукопожатия. Повторное подключение
+    /// того же устройства заменяет старое
+    pub fn connect(&self, hello: Hello, stream: &TcpStream) -> std::io::Result<Arc<Device>> {
+        let device = Arc::new(Device {
+            writer: Mutex::new(stream.try_clone()?),
+            hello,
+            next_seq: AtomicU32::new(0),
+            pending: Mutex::new(HashMap::new()),
+        });
+
+        self.devices
+            .lock()
+            .expect("Error locking mutex")
+            .insert(device.hello.serial.clone(), Arc::clone(&device));
+
+        Ok(device)
+    }
+
+    /// Убирает датчик, если его еще не заменило новое подключение.
+    /// Ожидающие команды получают [`CommandError::Disconnected`]
+    pub fn disconnect(&self, device: &Arc<Device>) {
+        let mut devices = self.devices.lock().expect("Error locking mutex");
+
+        if devices.get(&device.hello.serial).is_some_and(|d| Arc::ptr_eq(d, device)) {
+            devices.remove(&device.hello.serial);
+        }
+
+        device.pending.lock().expect("Error locking mutex").clear();
+    }
+
+    pub fn list(&self) -> Vec<Arc<Device>> {
+        self.devices
+            .lock()
+            .expect("Error locking mutex")
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Отправляет текстовую команду, например `interval 10`,
+    /// и ждет ответ датчика не дольше `ack_timeout` (по умолчанию [`ACK_TIMEOUT`])
+    pub fn send_command(&self, serial: &str, command: &str) -> Result<(), CommandError> {
+        let device = self.devices
+            .lock()
+            .expect("Error locking mutex")
+            .get(serial)
+            .cloned()
+            .ok_or(CommandError::UnknownDevice(serial.to_string()))?;
+
+        device.send(command, self.ack_timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::{Shutdown, TcpListener, TcpStream}, thread, time::Duration};
+
+    use gauge::types::{client::GaugeClient, command::Command, device::DeviceId, fire_alarm::{FireAlarm, FireAlarmCommand, FireAlarmState, FIRE_ALARM_ID}, frame::FrameDecoder, handshake::{self, Hello, FEATURE_COMMANDS, FEATURE_STATE}, DeserializedGauge, Gauge, GaugeCommand};
+
+    use super::{CommandError, Devices};
+
+    /// Принимает датчики так же, как сервер: каждый в своем `handle_client`
+    fn serve(devices: Devices) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        thread::spawn(move || for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            let devices = devices.clone();
+
+            thread::spawn(move || crate::handle_client(stream, devices));
+        });
+
+        address
+    }
+
+    fn wait_for(devices: &Devices, serial: &str, connected: bool) {
+        for _ in 0..100 {
+            if devices.list().iter().any(|d| d.hello().serial == serial) == connected {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("{serial} connected: {}", !connected);
+    }
+
+    /// Кадр состояния пожарной сигнализации устройства `device`
+    fn state(device: &str) -> Vec<u8> {
+        let mut alarm = FireAlarm::new("Hall".to_string(), FireAlarmState::Enabled);
+        alarm.set_device(device.parse::<DeviceId>().unwrap());
+
+        alarm.serialize().unwrap()
+    }
+
+    #[test]
+    fn state_frames_must_match_handshake() {
+        let hello = Hello::new(FIRE_ALARM_ID, "FA-0001", &[FEATURE_STATE]);
+        let parse = |frame: Vec<u8>| {
+            let mut decoder = FrameDecoder::new();
+            decoder.extend(&frame);
+            DeserializedGauge::parse(decoder.next_frame().unwrap().unwrap()).unwrap()
+        };
+
+        assert!(crate::check_device(&parse(state("FA-0001")), &hello).is_ok());
+        assert!(crate::check_device(&parse(state("FA-0002")), &hello).is_err());
+        assert!(crate::check_device(&parse(FireAlarm::new("Hall".to_string(), FireAlarmState::Enabled).serialize().unwrap()), &hello).is_err());
+    }
+
+    #[test]
+    fn commands_over_loopback() {
+        let devices = Devices::with_ack_timeout(Duration::from_millis(200));
+        let address = serve(devices.clone());
+
+        // Приветствие с неверным идентификатором отклоняется, датчик не регистрируется
+        let mut stream = TcpStream::connect(&address).unwrap();
+        let hello = Hello::new(FIRE_ALARM_ID, "Living room alarm", &[FEATURE_COMMANDS]);
+        assert!(handshake::greet(&mut stream, &hello).is_err());
+        assert!(devices.list().is_empty());
+
+        let hello = Hello::new(FIRE_ALARM_ID, "FA-0001", &[FEATURE_STATE, FEATURE_COMMANDS]);
+        let client = GaugeClient::new(address.clone(), hello, |command, _| {
+            match FireAlarmCommand::parse_command(command).map_err(|e| e.to_string())? {
+                FireAlarmCommand::Enable => Ok(()),
+                FireAlarmCommand::SelfTest => {
+                    thread::sleep(Duration::from_millis(500));
+                    Ok(())
+                },
+                _ => Err("alarm is not sounding".to_string()),
+            }
+        });
+        client.connect().unwrap();
+        wait_for(&devices, "FA-0001", true);
+
+        // Чужое состояние отбрасывается, но соединение остается
+        client.send(&state("FA-0009")).unwrap();
+        client.send(&state("FA-0001")).unwrap();
+
+        assert!(devices.send_command("FA-0001", "enable").is_ok());
+        assert!(matches!(
+            devices.send_command("FA-0001", "silence"),
+            Err(CommandError::Failed(reason)) if reason == "alarm is not sounding"
+        ));
+        assert!(matches!(devices.send_command("FA-0001", "interval 5"), Err(CommandError::Invalid(_))));
+        assert!(matches!(devices.send_command("FA-0001", "self-test"), Err(CommandError::Timeout(_))));
+        assert!(matches!(devices.send_command("FA-0002", "enable"), Err(CommandError::UnknownDevice(_))));
+
+        // Датчик получает команду и обрывает соединение, не ответив
+        let mut stream = TcpStream::connect(&address).unwrap();
+        let hello = Hello::new(FIRE_ALARM_ID, "FA-0002", &[FEATURE_COMMANDS]);
+        handshake::greet(&mut stream, &hello).unwrap();
+        wait_for(&devices, "FA-0002", true);
+
+        let sender = devices.clone();
+        let pending = thread::spawn(move || sender.send_command("FA-0002", "disable"));
+
+        let command = FrameDecoder::new().read_frame(&mut stream).unwrap().unwrap();
+        assert_eq!(Command::parse(command).unwrap().command, FireAlarmCommand::Disable.serialize_command());
+        stream.shutdown(Shutdown::Both).unwrap();
+
+        assert!(matches!(pending.join().unwrap(), Err(CommandError::Disconnected)));
+        wait_for(&devices, "FA-0002", false);
+        assert!(matches!(devices.send_command("FA-0002", "enable"), Err(CommandError::UnknownDevice(_))));
+    }
+}
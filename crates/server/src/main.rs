@@ -1,10 +1,26 @@
 use std::net::{TcpListener, TcpStream};
-use std::io::Read;
 
 use gauge::helpers::read_gauge_by_id;
-use gauge::types::{SerializedGauge, DeserializedGauge};
+use gauge::types::{command::Message, frame::FrameDecoder, handshake::{self, Hello}, DeserializedGauge};
+
+use devices::Devices;
+
+mod devices;
+
+/// Кадр состояния должен нести тот же [`DeviceId`], что и рукопожатие,
+/// иначе показания одного устройства приписались бы другому
+fn check_device(gauge: &DeserializedGauge, hello: &Hello) -> Result<(), String> {
+    match gauge.try_device() {
+        Ok(Some(device)) if device.as_str() == hello.serial => Ok(()),
+        Ok(Some(device)) => Err(format!("frame from device {device}")),
+        Ok(None) => Err("frame without device id".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn read_gauge(gauge: DeserializedGauge, device: &str) {
+    print!("[{device}] ");
 
-fn read_gauge(gauge: DeserializedGauge) {
     match read_gauge_by_id(gauge) {
         Ok(reader_gauge) => {
             match reader_gauge {
@@ -26,20 +42,86 @@ fn read_gauge(gauge: DeserializedGauge) {
     }
 }
 
-fn handle_client(mut stream: TcpStream) {
-    let mut buffer = [0; 1024];
+/// После рукопожатия читает кадры, пока датчик не закроет соединение.
+/// После поврежденного кадра соединение закрывается (см. [`FrameDecoder`])
+fn handle_client(mut stream: TcpStream, devices: Devices) {
+    let mut decoder = FrameDecoder::new();
 
-    while match stream.read(&mut buffer) {
-        Ok(size) if size > 0 => {
-            let recieved_gauge = SerializedGauge::from(buffer.to_vec());
-            let deserialized_gauge = DeserializedGauge::parse(recieved_gauge);
-            
-            read_gauge(deserialized_gauge);
+    let hello = match handshake::accept(&mut stream, &mut decoder) {
+        Ok((hello, version)) => {
+            println!("Gauge {} connected, protocol version {version}", hello.serial);
+            hello
+        },
+        Err(e) => {
+            eprintln!("Handshake failed: {e}");
+            return;
+        },
+    };
 
-            true
+    let device = match devices.connect(hello.clone(), &stream) {
+        Ok(device) => device,
+        Err(e) => {
+            eprintln!("Error registering gauge {}: {e}", hello.serial);
+            return;
         },
-        _ => false,
-    } {}
+    };
+
+    loop {
+        match decoder.read_frame(&mut stream) {
+            Ok(Some(recieved_gauge)) => match Message::parse(recieved_gauge) {
+                Ok(Message::State(deserialized_gauge)) => match check_device(&deserialized_gauge, &hello) {
+                    Ok(()) => read_gauge(deserialized_gauge, &hello.serial),
+                    Err(e) => eprintln!("Gauge {} state dropped: {e}", hello.serial),
+                },
+                Ok(Message::Ack(ack)) => device.acknowledge(ack),
+                Err(e) => eprintln!("Error reading gauge frame: {e}"),
+            },
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("Error reading gauge {} frame, closing connection: {e}", hello.serial);
+                break;
+            },
+        }
+    }
+
+    devices.disconnect(&device);
+    println!("Gauge {} disconnected", hello.serial);
+
+    if decoder.buffered() > 0 {
+        eprintln!("Connection closed in the middle of a frame, {} bytes dropped", decoder.buffered());
+    }
+}
+
+/// Управление датчиками из stdin: `devices` или `<device id> <command>`
+fn console(devices: Devices) {
+    loop {
+        println!("devices - list connected gauges, <device id> <command> - send command");
+
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => (),
+        }
+
+        match input.trim().split_once(' ') {
+            None if input.trim() == "devices" => {
+                for device in devices.list() {
+                    let hello = device.hello();
+                    println!(
+                        "{} {} [{}]",
+                        hello.serial,
+                        String::from_utf8_lossy(&hello.gauge_type),
+                        hello.features.join(", ")
+                    );
+                }
+            },
+            Some((serial, command)) => match devices.send_command(serial, command) {
+                Ok(()) => println!("{serial}: {command} done"),
+                Err(e) => eprintln!("{serial}: {command} {e}"),
+            },
+            None => println!("Invalid input"),
+        }
+    }
 }
 
 fn main() {
@@ -53,11 +135,16 @@ fn main() {
 
     println!("Created listener on {adress} adress {port} port");
 
+    let devices = Devices::default();
+    let console_devices = devices.clone();
+    std::thread::spawn(move || console(console_devices));
+
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
+                let devices = devices.clone();
                 std::thread::spawn(move || {
-                    handle_client(stream);
+                    handle_client(stream, devices);
                 });
             },
             Err(e) => eprintln!("Failed to accept connection: {e}"),
@@ -1,31 +1,91 @@
-use std::{time::{Duration, Instant}, thread, io::{Write,Read, self}, sync::{Arc, Mutex}, net::TcpStream};
+use std::{time::{Duration, Instant}, thread, io, sync::{Arc, Mutex}};
 
-use gauge::types::{temperature_gauge::{TemperatureGauge, TemperatureGaugeState}, Gauge};
+use gauge::types::{client::GaugeClient, device::DeviceId, handshake::{Hello, FEATURE_COMMANDS, FEATURE_STATE}, temperature_gauge::{TemperatureGauge, TemperatureGaugeCommand, TemperatureGaugeState, TEMPERATURE_GAUGE_ID}, Gauge, GaugeCommand};
 
+/// Интервал отправки температуры по умолчанию, меняется командой сервера
 const INTERVAL: Duration = Duration::from_secs(5);
 
+/// Выполняет команду сервера, `Ok(true)`, если состояние изменилось
+fn execute(gauge: &mut TemperatureGauge, interval: &Mutex<Duration>, command: TemperatureGaugeCommand) -> Result<bool, String> {
+    match (command, gauge.state()) {
+        (TemperatureGaugeCommand::Enable, TemperatureGaugeState::Disabled) => {
+            gauge.set_state(TemperatureGaugeState::Enabled);
+            Ok(true)
+        },
+        (TemperatureGaugeCommand::Enable, _) => Ok(false),
+        (TemperatureGaugeCommand::Disable, TemperatureGaugeState::Disabled) => Ok(false),
+        (TemperatureGaugeCommand::Disable, _) => {
+            gauge.set_state(TemperatureGaugeState::Disabled);
+            Ok(true)
+        },
+        (TemperatureGaugeCommand::SetInterval(new_interval), _) => {
+            *interval.lock().expect("Error locking mutex") = new_interval;
+            println!("Reporting every {} seconds", new_interval.as_secs());
+            Ok(false)
+        },
+        (TemperatureGaugeCommand::SelfTest, TemperatureGaugeState::Disabled) => Err("gauge disabled".to_string()),
+        (TemperatureGaugeCommand::SelfTest, _) => {
+            println!("Self-test passed");
+            Ok(false)
+        },
+    }
+}
+
 fn main() {
+    let server_adress = std::env::var("SERVER_ADRESS")
+        .expect("SERVER_ADRESS variable not defined!");
+    // Идентификатор создается при первом запуске и потом не меняется
+    let device_id_file = std::env::var("DEVICE_ID_FILE")
+        .unwrap_or("device_id".to_string());
+    let device = DeviceId::load_or_create(&device_id_file)
+        .expect("Error loading device id");
+    println!("Device id: {device}");
+
     let temperature = Arc::new(Mutex::new(36.6));
     let temperature_clone = Arc::clone(&temperature);
-
-    let temperature_gauge = 
-        Arc::new(
-            Mutex::new(
-                TemperatureGauge::new(
-                    "Living room".to_string(), 
-                    TemperatureGaugeState::Disabled
-                )
-            )
-        );
+    let interval = Arc::new(Mutex::new(INTERVAL));
+
+    let mut temperature_gauge = TemperatureGauge::new(
+        "Living room".to_string(),
+        TemperatureGaugeState::Disabled
+    );
+    temperature_gauge.set_device(device.clone());
+    let temperature_gauge = Arc::new(Mutex::new(temperature_gauge));
     let gauge_clone = Arc::clone(&temperature_gauge);
 
+    let commanded_gauge = Arc::clone(&temperature_gauge);
+    let commanded_interval = Arc::clone(&interval);
+    let hello = Hello::new(TEMPERATURE_GAUGE_ID, device.as_str(), &[FEATURE_STATE, FEATURE_COMMANDS]);
+    let client = GaugeClient::new(server_adress, hello, move |command, client| {
+        let command = TemperatureGaugeCommand::parse_command(command)
+            .map_err(|e| e.to_string())?;
+        let mut gauge = commanded_gauge.lock().expect("Error locking mutex");
+
+        println!("Server command: {command}");
+
+        if execute(&mut gauge, &commanded_interval, command)? {
+            println!("{gauge}");
+            gauge.serialize()
+                .and_then(|bytes| client.send(&bytes))
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    });
+
+    if let Err(e) = client.connect() {
+        eprintln!("Error during proccesing gauge: {}",e)
+    }
+
+    let reporting_client = client.clone();
     thread::spawn(move || {
-        let mut next_time = Instant::now() + INTERVAL;
+        let mut last_time = Instant::now();
 
         loop {
             let now = Instant::now();
 
-            if next_time <= now {
+            // Новый интервал отсчитывается от последней отправки
+            if last_time + *interval.lock().expect("Error locking mutex") <= now {
                 let mut gauge = gauge_clone.lock().expect("Error locking mutex");
 
                 match gauge.state() {
@@ -34,14 +94,14 @@ fn main() {
                             TemperatureGaugeState::ReadedTemperarure(*temperature.lock().expect("Error locking mutex"))
                         );
 
-                        send_gauge(&mut gauge);
+                        send_gauge(&mut gauge, &reporting_client);
 
                         gauge.set_state(TemperatureGaugeState::Enabled);
                     }
                     _ => ()
                 }
 
-                next_time += INTERVAL;
+                last_time = now;
             }
 
 
@@ -65,12 +125,12 @@ fn main() {
                     TemperatureGaugeState::Disabled => {
                         gauge.set_state(TemperatureGaugeState::Enabled);
                         println!("Gauge enabled");
-                        send_gauge(&mut gauge);
+                        send_gauge(&mut gauge, &client);
                     },
                     TemperatureGaugeState::Enabled => {
                         gauge.set_state(TemperatureGaugeState::Disabled);
                         println!("Gauge disabled");
-                        send_gauge(&mut gauge);
+                        send_gauge(&mut gauge, &client);
                     },
                     _ => ()
                 }
@@ -110,13 +170,10 @@ fn main() {
     }
 }
 
-fn send_gauge(gauge: &mut TemperatureGauge) {
+fn send_gauge(gauge: &mut TemperatureGauge, client: &GaugeClient) {
 
     let result = || -> Result<(),Box<dyn std::error::Error>> {
-        let address = std::env::var("SERVER_ADRESS")
-            .map_err(|_e| "SERVER_ADRESS var not specified")?;
-        TcpStream::connect(address)?
-            .write(&gauge.serialize())?;
+        client.send(&gauge.serialize()?)?;
 
         Ok(())
     }();